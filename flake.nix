@@ -26,6 +26,11 @@
 
             sqlite
 
+            # for the ocr feature
+            tesseract
+            leptonica
+            clang
+
             cargo
             rustc
             rustfmt
@@ -36,6 +41,7 @@
             nixpkgs-fmt
           ];
           RUST_SRC_PATH = rustPlatform.rustLibSrc;
+          LIBCLANG_PATH = "${llvmPackages.libclang.lib}/lib";
         };
       });
 }
@@ -0,0 +1,260 @@
+//! HTTP API exposing the sticker index to other tools
+//!
+//! - `GET /stickers?q=<query>&offset=<n>&lang=<code>` searches the stickers the way inline queries
+//!   do, preferring the tags in the optional language, in the `default_sort_order` and with the
+//!   `default_safe_search`
+//! - `GET /stickers/<id>/tags` lists the approved tags of a sticker
+//! - `POST /tags` tags a sticker with `{"sticker_id": <id>, "tags": [..]}`, which needs the
+//!   `Authorization: Bearer <token>` header with a token issued by /token; the tags that someone
+//!   else added to the sticker already are voted for instead
+//!
+//! The web dashboard for the admin is served along with it, see [`dashboard`].
+
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    extract::{Extension, Path, Query},
+    http::{
+        header::{AUTHORIZATION, WWW_AUTHENTICATE},
+        HeaderMap, StatusCode,
+    },
+    response::{Headers, IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use itertools::Itertools;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{error, info};
+
+use crate::{auth, language, model, query, BotError, DataStore, UserError};
+
+mod dashboard;
+
+/// Number of stickers returned for a search at once
+const PAGE_SIZE: usize = 50;
+
+/// Serves the API on `port` in a background task
+pub fn spawn(store: Arc<DataStore>, port: u16) {
+    let app = Router::new()
+        .route("/stickers", get(search))
+        .route("/stickers/:id/tags", get(sticker_tags))
+        .route("/tags", post(tag))
+        .merge(dashboard::routes())
+        .layer(Extension(store));
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    tokio::spawn(async move {
+        info!("Serving the API on {addr}");
+        if let Err(e) = axum::Server::bind(&addr)
+            .serve(app.into_make_service())
+            .await
+        {
+            error!("API server error: {e}");
+        }
+    });
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchParams {
+    q: String,
+    #[serde(default)]
+    offset: usize,
+    /// Two-letter language code whose tags are preferred, e.g. `en`
+    lang: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ApiSticker {
+    id: i32,
+    file_id: String,
+    set_name: String,
+    emoji: Option<String>,
+    popularity: i64,
+    /// `regular`, `mask`, `custom_emoji`, `animation`, `photo` or `video`
+    kind: String,
+}
+
+impl From<model::sticker::Model> for ApiSticker {
+    fn from(sticker: model::sticker::Model) -> Self {
+        Self {
+            id: sticker.id,
+            file_id: sticker.file_id,
+            set_name: sticker.set_name,
+            emoji: sticker.emoji,
+            popularity: sticker.popularity,
+            kind: sticker.kind,
+        }
+    }
+}
+
+/// Searches the stickers, which are not personalized for anyone
+async fn search(
+    Query(params): Query<SearchParams>,
+    Extension(store): Extension<Arc<DataStore>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let mut search_query = query::SearchQuery::parse(&params.q);
+    search_query.drop_noise(params.lang.as_deref());
+    search_query.sort_order = store.default_sort_order;
+    search_query.apply_safe_search(store.default_safe_search);
+    crate::limit_to_page(&mut search_query, params.offset, PAGE_SIZE);
+    let stickers = crate::find_stickers(&store, &search_query, 0, params.lang.as_deref()).await?;
+
+    let has_next_page = stickers.len() > params.offset + PAGE_SIZE;
+    let stickers = stickers
+        .into_iter()
+        .skip(params.offset)
+        .take(PAGE_SIZE)
+        .map(ApiSticker::from)
+        .collect_vec();
+    let next_offset = has_next_page.then_some(params.offset + PAGE_SIZE);
+
+    Ok(Json(json!({
+        "stickers": stickers,
+        "next_offset": next_offset,
+    })))
+}
+
+async fn sticker_tags(
+    Path(sticker_id): Path<i32>,
+    Extension(store): Extension<Arc<DataStore>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    model::sticker::Entity::find_by_id(sticker_id)
+        .one(&store.db)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let tags = model::tagged_sticker::Entity::find()
+        .filter(model::tagged_sticker::Column::StickerId.eq(sticker_id))
+        .filter(model::tagged_sticker::Column::Approved.eq(true))
+        // tags added in groups are for their members only, and private tags for their taggers
+        .filter(model::tagged_sticker::Column::ChatId.is_null())
+        .filter(model::tagged_sticker::Column::Visibility.eq(model::tagged_sticker::PUBLIC))
+        .filter(model::tagged_sticker::Column::DeletedAt.is_null())
+        .order_by_asc(model::tagged_sticker::Column::Id)
+        .all(&store.db)
+        .await?
+        .into_iter()
+        .map(|tagged| tagged.tag)
+        .collect_vec();
+
+    Ok(Json(json!({ "sticker_id": sticker_id, "tags": tags })))
+}
+
+#[derive(Debug, Deserialize)]
+struct TagRequest {
+    sticker_id: i32,
+    tags: Vec<String>,
+}
+
+/// Tags an indexed sticker on behalf of the tagger owning the token
+async fn tag(
+    headers: HeaderMap,
+    Extension(store): Extension<Arc<DataStore>>,
+    Json(request): Json<TagRequest>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(ApiError::Unauthorized)?;
+    let tagger = auth::authorize_token(&store.db, token.trim()).await?;
+    store.throttle(tagger.user.user_id)?;
+
+    // tags are split and normalized the same way as the ones given to /tag
+    let parsed_tags = language::parse_tags(&request.tags.join(" "));
+    if parsed_tags.is_empty() {
+        return Err(ApiError::BadRequest("no tags given"));
+    }
+
+    let txn = store.begin().await?;
+    model::sticker::Entity::find_by_id(request.sticker_id)
+        .one(&txn)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    // the tags added through the API aren't scoped to any chat
+    let added = crate::add_tags(
+        &txn,
+        &store,
+        &tagger,
+        request.sticker_id,
+        &parsed_tags,
+        None,
+    )
+    .await?;
+    txn.commit().await?;
+    store
+        .record_added_tags(tagger.user.user_id, request.sticker_id, &added)
+        .await;
+
+    info!(
+        "{username} tagged sticker {sticker_id} through the API with tags: {new_tags:?} (already present: {present_tags:?}, voted for: {voted_tags:?})",
+        username = tagger.user.username,
+        sticker_id = request.sticker_id,
+        new_tags = added.added,
+        present_tags = added.present,
+        voted_tags = added.voted,
+    );
+
+    Ok(Json(json!({
+        "added": added.added,
+        "already_present": added.present,
+        "voted": added.voted,
+        "approved": added.approved,
+    })))
+}
+
+/// Failures of API requests, which are responded to as `{"error": <reason>}`
+#[derive(Debug)]
+enum ApiError {
+    Unauthorized,
+    /// The dashboard was requested without the right credentials, which the browser asks for
+    NeedsLogin,
+    NotFound,
+    BadRequest(&'static str),
+    RateLimited,
+    Internal(BotError),
+}
+
+impl From<BotError> for ApiError {
+    fn from(e: BotError) -> Self {
+        match e {
+            BotError::User(UserError::Unauthorized(_)) => Self::Unauthorized,
+            BotError::User(UserError::RateLimited) => Self::RateLimited,
+            BotError::NoSuchSticker => Self::NotFound,
+            e => Self::Internal(e),
+        }
+    }
+}
+
+impl From<UserError> for ApiError {
+    fn from(e: UserError) -> Self {
+        BotError::from(e).into()
+    }
+}
+
+impl From<sea_orm::DbErr> for ApiError {
+    fn from(e: sea_orm::DbErr) -> Self {
+        Self::Internal(e.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, reason) = match self {
+            Self::Unauthorized => (StatusCode::UNAUTHORIZED, "invalid or missing token"),
+            Self::NeedsLogin => {
+                let headers = Headers([(WWW_AUTHENTICATE, "Basic realm=\"sticker search\"")]);
+                return (StatusCode::UNAUTHORIZED, headers, "login required").into_response();
+            }
+            Self::NotFound => (StatusCode::NOT_FOUND, "not found"),
+            Self::BadRequest(reason) => (StatusCode::BAD_REQUEST, reason),
+            Self::RateLimited => (StatusCode::TOO_MANY_REQUESTS, "rate limited"),
+            Self::Internal(e) => {
+                error!("Failed to handle API request: {e}");
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal error")
+            }
+        };
+        (status, Json(json!({ "error": reason }))).into_response()
+    }
+}
@@ -0,0 +1,391 @@
+//! Web dashboard for moderating the index, served along with the API under `/admin`
+//!
+//! The dashboard lists pending registrations, recent tags, open reports and the top queries, with
+//! buttons to approve or deny registrations, delete tags and dismiss reports. It's protected with
+//! HTTP basic auth, taking any of the secrets as the password under any username.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Form, Path},
+    http::{header::AUTHORIZATION, HeaderMap, Uri},
+    response::{Html, Redirect},
+    routing::{get, post},
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use html_escape::{encode_double_quoted_attribute as attr, encode_text as text};
+use itertools::Itertools;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, EntityTrait, IntoActiveModel, PaginatorTrait,
+    QueryFilter, QueryOrder, QuerySelect, QueryTrait,
+};
+use serde::Deserialize;
+use tracing::info;
+
+use super::ApiError;
+use crate::{audit_entry, auth, model, query, trash_tags, DataStore};
+
+/// Number of recent tags, open reports and top queries listed at most
+const LIST_SIZE: u64 = 50;
+
+/// Name recorded in the audit log for the actions taken in the dashboard
+const DASHBOARD_ACTOR: &str = "dashboard";
+
+pub fn routes() -> Router {
+    Router::new()
+        .route("/admin", get(page))
+        .route("/admin/users/:id/allow", post(allow_user))
+        .route("/admin/users/:id/deny", post(deny_user))
+        .route("/admin/tags/:id/delete", post(delete_tag))
+        .route("/admin/reports/:id/dismiss", post(dismiss_report))
+}
+
+/// Fields of the forms behind the buttons
+#[derive(Debug, Deserialize)]
+struct ActionForm {
+    /// Token proving that the form was sent from the dashboard, see [`csrf_token`]
+    csrf: String,
+}
+
+/// Checks the basic auth credentials of a request, whose password must be one of the secrets, and
+/// returns the hash of it
+fn authorize(headers: &HeaderMap, store: &DataStore) -> Result<String, ApiError> {
+    let credentials = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "))
+        .and_then(|encoded| STANDARD.decode(encoded.trim()).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .ok_or(ApiError::NeedsLogin)?;
+    match credentials.split_once(':') {
+        Some((_, password)) if store.is_secret(password) => Ok(auth::hash_token(password)),
+        _ => Err(ApiError::NeedsLogin),
+    }
+}
+
+/// Checks a request for an action, which must come from the dashboard itself: browsers send the
+/// credentials along with forms posted from any site, but those can't know the CSRF token
+fn authorize_action(
+    headers: &HeaderMap,
+    store: &DataStore,
+    form: &ActionForm,
+) -> Result<(), ApiError> {
+    let secret_hash = authorize(headers, store)?;
+    if form.csrf != csrf_token(&secret_hash) {
+        return Err(ApiError::BadRequest("invalid csrf token"));
+    }
+    Ok(())
+}
+
+/// Token put in the forms of the dashboard, which is derived from the secret logged in with
+fn csrf_token(secret_hash: &str) -> String {
+    auth::hash_token(&format!("csrf:{secret_hash}"))
+}
+
+/// Audit log entry for an action taken in the dashboard, to be completed with the targets
+fn dashboard_audit_entry(action: &str) -> model::audit_log::ActiveModel {
+    model::audit_log::ActiveModel {
+        actor_username: Set(Some(DASHBOARD_ACTOR.to_owned())),
+        ..audit_entry(action, None)
+    }
+}
+
+async fn page(
+    headers: HeaderMap,
+    Extension(store): Extension<Arc<DataStore>>,
+) -> Result<Html<String>, ApiError> {
+    let secret_hash = authorize(&headers, &store)?;
+    let csrf = csrf_token(&secret_hash);
+
+    // registrations without tags haven't been decided on yet, as opposed to denied taggers
+    let taggers = model::tagged_sticker::Entity::find()
+        .select_only()
+        .column(model::tagged_sticker::Column::TaggerId)
+        .into_query();
+    let pending_rows = model::user::Entity::find()
+        .filter(model::user::Column::Allowed.eq(false))
+        .filter(model::user::Column::Id.not_in_subquery(taggers))
+        .order_by_asc(model::user::Column::Id)
+        .limit(LIST_SIZE)
+        .all(&store.db)
+        .await?
+        .into_iter()
+        .map(|user| {
+            format!(
+                "<tr><td>@{username}</td><td>{allow}{deny}</td></tr>",
+                username = text(&user.username),
+                allow = button(&format!("/admin/users/{}/allow", user.id), "Approve", &csrf),
+                deny = button(&format!("/admin/users/{}/deny", user.id), "Deny", &csrf)
+            )
+        })
+        .collect_vec();
+
+    let recent_tags = model::tagged_sticker::Entity::find()
+        .filter(model::tagged_sticker::Column::DeletedAt.is_null())
+        .find_also_related(model::user::Entity)
+        .order_by_desc(model::tagged_sticker::Column::Id)
+        .limit(LIST_SIZE)
+        .all(&store.db)
+        .await?;
+    let recent_rows = recent_tags
+        .iter()
+        .map(|(tagged, tagger)| {
+            format!(
+                "<tr><td>{ts}</td><td>{sticker_id}</td><td>{tag}</td><td>@{tagger}</td><td>{status}</td><td>{delete}</td></tr>",
+                ts = tagged.ts.format("%Y-%m-%d %H:%M"),
+                sticker_id = tagged.sticker_id,
+                tag = text(&tagged.tag),
+                tagger = text(tagger.as_ref().map_or("<unknown>", |tagger| &tagger.username)),
+                status = if tagged.approved { "approved" } else { "pending" },
+                delete = button(&format!("/admin/tags/{}/delete", tagged.id), "Delete", &csrf)
+            )
+        })
+        .collect_vec();
+
+    let reports = model::report::Entity::find()
+        .filter(model::report::Column::Resolved.eq(false))
+        .order_by_asc(model::report::Column::Id)
+        .limit(LIST_SIZE)
+        .all(&store.db)
+        .await?;
+    let mut report_rows = vec![];
+    for report in reports {
+        let tags = model::tagged_sticker::Entity::find()
+            .filter(model::tagged_sticker::Column::StickerId.eq(report.sticker_id))
+            .filter(model::tagged_sticker::Column::DeletedAt.is_null())
+            .order_by_asc(model::tagged_sticker::Column::Id)
+            .all(&store.db)
+            .await?;
+        let tag_buttons = tags
+            .iter()
+            .map(|tagged| {
+                button(
+                    &format!("/admin/tags/{}/delete", tagged.id),
+                    &format!("Delete {}", tagged.tag),
+                    &csrf,
+                )
+            })
+            .join("");
+        report_rows.push(format!(
+            "<tr><td>{ts}</td><td>{sticker_id}</td><td>@{reporter}</td><td>{reason}</td><td>{tag_buttons}{dismiss}</td></tr>",
+            ts = report.ts.format("%Y-%m-%d %H:%M"),
+            sticker_id = report.sticker_id,
+            reporter = text(report.reporter_username.as_deref().unwrap_or("<unknown>")),
+            reason = text(report.reason.as_deref().unwrap_or("")),
+            dismiss = button(&format!("/admin/reports/{}/dismiss", report.id), "Dismiss", &csrf)
+        ));
+    }
+
+    let query_rows = crate::top_queries(&store.db, LIST_SIZE)
+        .await?
+        .into_iter()
+        .map(|(query, count)| format!("<tr><td>{}</td><td>{count}</td></tr>", text(&query)))
+        .collect_vec();
+
+    Ok(Html(format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Sticker search dashboard</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; }}
+table {{ border-collapse: collapse; margin-bottom: 2em; }}
+td, th {{ border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }}
+form {{ display: inline; }}
+</style>
+</head>
+<body>
+<h1>Sticker search dashboard</h1>
+{pending}
+{recent}
+{reports}
+{queries}
+</body>
+</html>
+"#,
+        pending = section("Pending registrations", &["User", ""], &pending_rows),
+        recent = section(
+            "Recent tags",
+            &["Time", "Sticker", "Tag", "Tagger", "Status", ""],
+            &recent_rows
+        ),
+        reports = section(
+            "Open reports",
+            &["Time", "Sticker", "Reporter", "Reason", ""],
+            &report_rows
+        ),
+        queries = section("Top queries", &["Query", "Times"], &query_rows),
+    )))
+}
+
+/// A titled table of the rows, or a note if there are none
+fn section(title: &str, headers: &[&str], rows: &[String]) -> String {
+    if rows.is_empty() {
+        return format!("<h2>{title}</h2>\n<p>Nothing here.</p>");
+    }
+    format!(
+        "<h2>{title}</h2>\n<table>\n<tr>{headers}</tr>\n{rows}\n</table>",
+        headers = headers
+            .iter()
+            .map(|header| format!("<th>{header}</th>"))
+            .join(""),
+        rows = rows.join("\n")
+    )
+}
+
+/// A button posting an action form to `action`
+fn button(action: &str, label: &str, csrf: &str) -> String {
+    format!(
+        r#"<form method="post" action="{action}"><input type="hidden" name="csrf" value="{csrf}"><button>{label}</button></form>"#,
+        action = attr(action),
+        csrf = attr(csrf),
+        label = text(label)
+    )
+}
+
+async fn allow_user(
+    headers: HeaderMap,
+    Path(user_id): Path<i32>,
+    Extension(store): Extension<Arc<DataStore>>,
+    Form(form): Form<ActionForm>,
+) -> Result<Redirect, ApiError> {
+    authorize_action(&headers, &store, &form)?;
+    let user = model::user::Entity::find_by_id(user_id)
+        .one(&store.db)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let txn = store.begin().await?;
+    let mut user_active = user.into_active_model();
+    user_active.allowed = Set(true);
+    let updated_user = user_active.update(&txn).await?;
+
+    model::audit_log::ActiveModel {
+        target_username: Set(Some(updated_user.username.clone())),
+        ..dashboard_audit_entry(model::audit_log::ALLOW)
+    }
+    .insert(&txn)
+    .await?;
+    txn.commit().await?;
+
+    info!("Allowed user {updated_user:?} to tag stickers via the dashboard");
+
+    Ok(Redirect::to(Uri::from_static("/admin")))
+}
+
+/// Denies a pending registration by deleting it, which the user may send again with /register
+async fn deny_user(
+    headers: HeaderMap,
+    Path(user_id): Path<i32>,
+    Extension(store): Extension<Arc<DataStore>>,
+    Form(form): Form<ActionForm>,
+) -> Result<Redirect, ApiError> {
+    authorize_action(&headers, &store, &form)?;
+    let user = model::user::Entity::find_by_id(user_id)
+        .one(&store.db)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    // the tags of taggers are deleted along with them, which is what /revoke is for
+    let tag_count = model::tagged_sticker::Entity::find()
+        .filter(model::tagged_sticker::Column::TaggerId.eq(user.id))
+        .count(&store.db)
+        .await?;
+    if user.allowed || tag_count > 0 {
+        return Err(ApiError::BadRequest("not a pending registration"));
+    }
+
+    let txn = store.begin().await?;
+    model::user::Entity::delete_by_id(user.id)
+        .exec(&txn)
+        .await?;
+    model::audit_log::ActiveModel {
+        target_username: Set(Some(user.username.clone())),
+        ..dashboard_audit_entry(model::audit_log::DENY)
+    }
+    .insert(&txn)
+    .await?;
+    txn.commit().await?;
+
+    info!("Denied the registration of {user:?} via the dashboard");
+
+    Ok(Redirect::to(Uri::from_static("/admin")))
+}
+
+async fn delete_tag(
+    headers: HeaderMap,
+    Path(tag_id): Path<i32>,
+    Extension(store): Extension<Arc<DataStore>>,
+    Form(form): Form<ActionForm>,
+) -> Result<Redirect, ApiError> {
+    authorize_action(&headers, &store, &form)?;
+
+    // the tag may have been deleted by pressing the button twice, which is fine
+    let tag_with_tagger = model::tagged_sticker::Entity::find_by_id(tag_id)
+        .filter(model::tagged_sticker::Column::DeletedAt.is_null())
+        .find_also_related(model::user::Entity)
+        .one(&store.db)
+        .await?;
+    if let Some((tagged, tagger)) = tag_with_tagger {
+        let txn = store.begin().await?;
+        trash_tags(None)
+            .filter(model::tagged_sticker::Column::Id.eq(tagged.id))
+            .exec(&txn)
+            .await?;
+
+        model::audit_log::ActiveModel {
+            sticker_id: Set(Some(tagged.sticker_id)),
+            target_username: Set(tagger.map(|tagger| tagger.username)),
+            tags: Set(Some(query::quote_term(&tagged.tag))),
+            ..dashboard_audit_entry(model::audit_log::UNTAG)
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+        store.invalidate_query_cache();
+
+        info!(
+            "Deleted tag {tag:?} from sticker {sticker_id} via the dashboard",
+            tag = tagged.tag,
+            sticker_id = tagged.sticker_id
+        );
+    }
+
+    Ok(Redirect::to(Uri::from_static("/admin")))
+}
+
+async fn dismiss_report(
+    headers: HeaderMap,
+    Path(report_id): Path<i32>,
+    Extension(store): Extension<Arc<DataStore>>,
+    Form(form): Form<ActionForm>,
+) -> Result<Redirect, ApiError> {
+    authorize_action(&headers, &store, &form)?;
+    let report = match model::report::Entity::find_by_id(report_id)
+        .one(&store.db)
+        .await?
+    {
+        Some(report) if report.resolved == false => report,
+        _ => return Ok(Redirect::to(Uri::from_static("/admin"))),
+    };
+
+    let txn = store.begin().await?;
+    let mut report_active = report.into_active_model();
+    report_active.resolved = Set(true);
+    let report = report_active.update(&txn).await?;
+
+    model::audit_log::ActiveModel {
+        sticker_id: Set(Some(report.sticker_id)),
+        target_username: Set(report.reporter_username.clone()),
+        ..dashboard_audit_entry(model::audit_log::REPORT_DISMISS)
+    }
+    .insert(&txn)
+    .await?;
+    txn.commit().await?;
+
+    info!("Dismissed report {report_id} via the dashboard");
+
+    Ok(Redirect::to(Uri::from_static("/admin")))
+}
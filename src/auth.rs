@@ -0,0 +1,130 @@
+//! Authorization of the commands that modify the index
+//!
+//! Handlers requiring a permission take the corresponding proof as an argument, which can only
+//! be obtained from the functions in this module, so that the checks can't be forgotten.
+
+use rand::{distributions::Alphanumeric, Rng};
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter,
+};
+use sha2::{Digest, Sha256};
+use teloxide::types::{Message, User};
+
+use crate::{model, reputation, strings, BotError};
+
+/// A registered user who has been allowed to tag stickers
+#[derive(Debug, Clone)]
+pub struct AuthorizedTagger {
+    pub user: model::user::Model,
+}
+
+impl AuthorizedTagger {
+    /// Whether the tags of the tagger are approved right away, which is the case once their
+    /// [`reputation`] reaches `threshold`, e.g. by having that many tags approved by a moderator
+    pub async fn is_trusted(
+        &self,
+        db: &impl ConnectionTrait,
+        threshold: u64,
+    ) -> Result<bool, DbErr> {
+        if threshold == 0 {
+            return Ok(true);
+        }
+
+        let reputation = reputation::of_tagger(db, self.user.id).await?;
+        Ok(reputation >= threshold as f64)
+    }
+}
+
+/// Length of the API tokens, which are alphanumeric
+const API_TOKEN_LEN: usize = 32;
+
+/// Reasons for refusing a command
+#[derive(Debug, thiserror::Error)]
+pub enum AuthError {
+    /// Telegram didn't tell who sent the message, e.g. in channels
+    #[error("{}", strings::SENDER_UNKNOWN)]
+    SenderUnknown,
+
+    /// The sender hasn't registered with /register
+    #[error("{}", strings::TAG_NOT_AUTHORIZED)]
+    NotRegistered,
+
+    /// The sender has registered, but hasn't been allowed by the admin (yet)
+    #[error("{}", strings::TAG_NOT_AUTHORIZED)]
+    NotAllowed,
+
+    /// The API token is unknown, or its tagger is no longer allowed
+    #[error("Invalid API token")]
+    InvalidToken,
+}
+
+/// Checks that the sender of the message is a known, registered and allowed tagger
+pub async fn authorize_tagger(
+    db: &DatabaseConnection,
+    message: &Message,
+) -> Result<AuthorizedTagger, BotError> {
+    let sender = message.from.as_ref().ok_or(AuthError::SenderUnknown)?;
+    authorize_tagger_user(db, sender).await
+}
+
+/// Checks that the user, e.g. the one who pressed a button, is a registered and allowed tagger
+pub async fn authorize_tagger_user(
+    db: &DatabaseConnection,
+    sender: &User,
+) -> Result<AuthorizedTagger, BotError> {
+    let mut user = model::user::Entity::find()
+        .filter(model::user::Column::UserId.eq(sender.id.0 as i64))
+        .one(db)
+        .await?
+        .ok_or(AuthError::NotRegistered)?;
+    if user.allowed == false {
+        return Err(AuthError::NotAllowed.into());
+    }
+    // the admins are seeded without a username, which is filled in once they show up
+    match &sender.username {
+        Some(username) if user.username.is_empty() => {
+            model::user::Entity::update_many()
+                .col_expr(model::user::Column::Username, Expr::value(username.clone()))
+                .filter(model::user::Column::Id.eq(user.id))
+                .exec(db)
+                .await?;
+            user.username = username.clone();
+        }
+        _ => {}
+    }
+
+    Ok(AuthorizedTagger { user })
+}
+
+/// Checks that the API token belongs to an allowed tagger
+pub async fn authorize_token(
+    db: &DatabaseConnection,
+    token: &str,
+) -> Result<AuthorizedTagger, BotError> {
+    let token = model::api_token::Entity::find_by_id(hash_token(token))
+        .one(db)
+        .await?
+        .ok_or(AuthError::InvalidToken)?;
+    let user = model::user::Entity::find_by_id(token.user_id)
+        .one(db)
+        .await?
+        .filter(|user| user.allowed)
+        .ok_or(AuthError::InvalidToken)?;
+
+    Ok(AuthorizedTagger { user })
+}
+
+/// Generates a new API token, which is only ever shown to its tagger
+pub fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(API_TOKEN_LEN)
+        .map(char::from)
+        .collect()
+}
+
+/// Hash under which an API token is stored, so that a leaked database doesn't leak the tokens
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,136 @@
+//! Scheduled backups of the index to Telegram
+//!
+//! The index is exported the same way as by /export, and sent as a JSON document to the backup
+//! chat (the admin chat unless `backup_chat_id` is set), from where it can be restored with
+//! /import on any database. Each backup sent is noted in `backup_log`, which only keeps the
+//! `backup_retention` latest ones. The messages of the backups pruned from it are deleted from the
+//! chat as well, as long as Telegram lets bots delete them, i.e. for 48 hours. Backups sent less
+//! often than that are only pruned from `backup_log`, and stay in the chat.
+
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use itertools::Itertools;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use teloxide::{
+    prelude::*,
+    types::{InputFile, MessageId},
+};
+use tracing::{debug, error, info, warn};
+
+use crate::{claim_scheduled_job, dump, jitter, model, Bot, BotError, DataStore};
+
+/// Name of the scheduled job, which only one of several instances sharing the database runs
+const BACKUP_JOB: &str = "backup";
+/// Age up to which bots may delete their messages
+const DELETABLE_SECS: u64 = 48 * 60 * 60;
+
+/// Sends a backup to the chat in a background task every `interval`, unless another instance of
+/// the bot sharing the database has done so recently
+pub fn spawn(
+    bot: Bot,
+    store: Arc<DataStore>,
+    chat_id: ChatId,
+    interval: Duration,
+    retention: usize,
+) {
+    if interval.as_secs() * retention as u64 >= DELETABLE_SECS {
+        warn!(
+            "The backups older than {retention} intervals are too old for the bot to delete them \
+             from the chat, so they're only pruned from backup_log"
+        );
+    }
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            // spread out the attempts of instances started at the same time
+            tokio::time::sleep(jitter(interval / 10)).await;
+            match claim_scheduled_job(&store.db, BACKUP_JOB, interval).await {
+                Ok(true) => {
+                    if let Err(e) = run(&bot, &store.db, chat_id, retention).await {
+                        error!("Failed to back up the index: {e}");
+                        store.errors.record(e.kind(), &e);
+                    }
+                }
+                Ok(false) => debug!("The index was backed up by another instance"),
+                Err(e) => error!("Failed to claim the backup: {e}"),
+            }
+        }
+    });
+}
+
+/// Sends a backup of the index to the chat, and deletes the backups sent before that are no
+/// longer among the `retention` latest ones
+pub async fn run(
+    bot: &Bot,
+    db: &DatabaseConnection,
+    chat_id: ChatId,
+    retention: usize,
+) -> Result<(), BotError> {
+    let index = dump::export(db).await?;
+    let json = serde_json::to_vec(&index)?;
+    let size = json.len();
+
+    let now = Utc::now();
+    let file_name = format!("stickers-backup-{}.json", now.format("%Y%m%d-%H%M%S"));
+    let caption = format!(
+        "Backup of {users} users and {stickers} stickers, restorable with /import",
+        users = index.users.len(),
+        stickers = index.stickers.len()
+    );
+    let document = InputFile::memory(json).file_name(file_name.clone());
+    let sent = bot
+        .send_document(chat_id, document)
+        .caption(caption)
+        .send()
+        .await?;
+    model::backup_log::ActiveModel {
+        chat_id: Set(chat_id.0),
+        message_id: Set(sent.id.0),
+        file_name: Set(file_name.clone()),
+        size: Set(size as i64),
+        ts: Set(now),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    info!("Sent backup {file_name} of {size} bytes");
+
+    // there are only as many backups noted as are retained, plus the one just sent
+    let expired = model::backup_log::Entity::find()
+        .order_by_desc(model::backup_log::Column::Ts)
+        .order_by_desc(model::backup_log::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .skip(retention)
+        .collect_vec();
+    // the messages of old backups may be gone already, or too old for the bot to delete, which
+    // leaves them to whoever manages the chat
+    let deletable_since = now - chrono::Duration::seconds(DELETABLE_SECS as i64);
+    for backup in &expired {
+        if backup.ts < deletable_since {
+            debug!("Backup {} is too old to be deleted", backup.file_name);
+            continue;
+        }
+        let deleted = bot
+            .delete_message(ChatId(backup.chat_id), MessageId(backup.message_id))
+            .send()
+            .await;
+        if let Err(e) = deleted {
+            warn!("Failed to delete backup {}: {e}", backup.file_name);
+        }
+    }
+    if expired.is_empty() == false {
+        model::backup_log::Entity::delete_many()
+            .filter(model::backup_log::Column::Id.is_in(expired.iter().map(|backup| backup.id)))
+            .exec(db)
+            .await?;
+        info!("Pruned {} old backups", expired.len());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+//! Registration of the commands with Telegram, which lets clients autocomplete them
+//!
+//! Private chats get every command besides the ones for the admin, groups only the ones that make
+//! sense there, e.g. the ones replied to stickers, and the admin chat gets all of them. The
+//! descriptions are translated into the language of each translation in [`crate::i18n`].
+
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+use teloxide::{
+    prelude::*,
+    types::{BotCommand, BotCommandScope, Recipient},
+    utils::command::BotCommands,
+};
+use tracing::info;
+
+use crate::{i18n, Bot, BotError, Command};
+
+/// Commands that need the secret or the admin chat
+const ADMIN_COMMANDS: &[&str] = &[
+    "allow",
+    "deny",
+    "revoke",
+    "chown",
+    "audit",
+    "export",
+    "import",
+    "stats",
+    "refreshset",
+    "purgedead",
+    "review",
+    "cleartags",
+    "retag",
+    "merge",
+];
+
+/// Commands offered in groups
+const GROUP_COMMANDS: &[&str] = &[
+    "tag",
+    "tagset",
+    "untag",
+    "undo",
+    "restore",
+    "unpropagate",
+    "listtags",
+    "similar",
+    "fav",
+    "report",
+    "vote",
+    "set",
+    "leaderboard",
+    "help",
+];
+
+/// Sets the commands of each kind of chat, in English and every translated language
+pub async fn register(bot: &Bot, admin_chat_id: Option<ChatId>) -> Result<(), BotError> {
+    // the derived commands come with the prefix, which Telegram doesn't take
+    let commands = Command::bot_commands()
+        .into_iter()
+        .map(|command| {
+            BotCommand::new(command.command.trim_start_matches('/'), command.description)
+        })
+        .collect_vec();
+    let mut scopes = vec![
+        (
+            BotCommandScope::AllPrivateChats,
+            commands
+                .iter()
+                .filter(|command| ADMIN_COMMANDS.contains(&command.command.as_str()) == false)
+                .cloned()
+                .collect_vec(),
+        ),
+        (
+            BotCommandScope::AllGroupChats,
+            commands
+                .iter()
+                .filter(|command| GROUP_COMMANDS.contains(&command.command.as_str()))
+                .cloned()
+                .collect_vec(),
+        ),
+    ];
+    if let Some(chat_id) = admin_chat_id {
+        let scope = BotCommandScope::Chat {
+            chat_id: Recipient::Id(chat_id),
+        };
+        scopes.push((scope, commands));
+    }
+
+    // Telegram only takes two-letter language codes, so the scripts and regions of a language
+    // share the commands of the first one, e.g. `zh` gets the ones of `zh-hant`
+    let mut languages = BTreeMap::new();
+    for language in i18n::languages() {
+        let primary_language = language.split('-').next().unwrap_or_default();
+        if primary_language.len() == 2 {
+            languages.entry(primary_language).or_insert(language);
+        }
+    }
+
+    for (scope, commands) in &scopes {
+        bot.set_my_commands(commands.clone())
+            .scope(scope.clone())
+            .send()
+            .await?;
+        for (&language_code, &language) in &languages {
+            let translated = commands.iter().map(|command| {
+                BotCommand::new(
+                    command.command.clone(),
+                    i18n::translate(Some(language), &command.description),
+                )
+            });
+            bot.set_my_commands(translated)
+                .scope(scope.clone())
+                .language_code(language_code)
+                .send()
+                .await?;
+        }
+    }
+    info!(
+        "Registered the commands with Telegram in {n} languages besides English",
+        n = languages.len()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listed_commands_exist() {
+        let commands = Command::bot_commands()
+            .into_iter()
+            .map(|command| command.command.trim_start_matches('/').to_owned())
+            .collect_vec();
+        for name in ADMIN_COMMANDS.iter().chain(GROUP_COMMANDS) {
+            assert!(
+                commands.iter().any(|command| command == name),
+                "/{name} to be a command"
+            );
+        }
+    }
+}
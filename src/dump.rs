@@ -0,0 +1,269 @@
+//! Portable JSON dump of the sticker index
+//!
+//! Database row ids are not included in the dump. Stickers are identified by their
+//! `file_unique_id`, and taggers by their Telegram user id, which are stable across databases.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, Set,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{language, model, query};
+
+/// Version of the dump format, bumped on incompatible changes
+pub const DUMP_VERSION: u32 = 1;
+
+/// Number of rows inserted or selected per statement, to keep the number of bind parameters low
+const CHUNK_SIZE: usize = 1000;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Dump {
+    pub version: u32,
+    pub users: Vec<User>,
+    pub stickers: Vec<Sticker>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct User {
+    pub user_id: i64,
+    pub username: String,
+    pub allowed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Sticker {
+    pub file_unique_id: String,
+    pub file_id: String,
+    pub set_name: String,
+    pub popularity: i64,
+    pub emoji: Option<String>,
+    /// Missing in dumps made before the time of indexing was recorded
+    #[serde(default)]
+    pub indexed_at: Option<DateTime<Utc>>,
+    /// Kind of the sticker, e.g. `custom_emoji`; missing in dumps made before custom emoji were
+    /// indexed, which only had regular stickers
+    #[serde(default = "regular_by_default")]
+    pub kind: String,
+    pub tags: Vec<Tag>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Tag {
+    pub tag: String,
+    /// Telegram user id of the tagger
+    pub tagger_user_id: i64,
+    pub ts: DateTime<Utc>,
+    /// Whether the tag has been approved; missing in dumps made before tags were reviewed
+    #[serde(default = "approved_by_default")]
+    pub approved: bool,
+    /// Language of the tag, if known; missing in dumps made before tags had languages, whose
+    /// languages are detected again on import
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Group chat the tag is scoped to, if any; missing in dumps made before tags were scoped
+    #[serde(default)]
+    pub chat_id: Option<i64>,
+    /// `public` or `private`; missing in dumps made before tags could be private
+    #[serde(default = "public_by_default")]
+    pub visibility: String,
+}
+
+fn approved_by_default() -> bool {
+    true
+}
+
+fn regular_by_default() -> String {
+    model::sticker::REGULAR.to_owned()
+}
+
+fn public_by_default() -> String {
+    model::tagged_sticker::PUBLIC.to_owned()
+}
+
+/// Reads the whole index from the database
+pub async fn export(db: &DatabaseConnection) -> Result<Dump, DbErr> {
+    let users = model::user::Entity::find().all(db).await?;
+    let stickers = model::sticker::Entity::find().all(db).await?;
+    // the tags in the trash are left behind
+    let tagged_stickers = model::tagged_sticker::Entity::find()
+        .filter(model::tagged_sticker::Column::DeletedAt.is_null())
+        .all(db)
+        .await?;
+
+    let user_id_for_tagger_id: HashMap<i32, i64> =
+        users.iter().map(|user| (user.id, user.user_id)).collect();
+    let mut tags_for_sticker_id: HashMap<i32, Vec<Tag>> = HashMap::new();
+    for tagged in tagged_stickers {
+        // tags of users that no longer exist can't be attributed to anyone, and are dropped
+        if let Some(&tagger_user_id) = user_id_for_tagger_id.get(&tagged.tagger_id) {
+            tags_for_sticker_id
+                .entry(tagged.sticker_id)
+                .or_default()
+                .push(Tag {
+                    tag: tagged.tag,
+                    tagger_user_id,
+                    ts: tagged.ts,
+                    approved: tagged.approved,
+                    language: tagged.language,
+                    chat_id: tagged.chat_id,
+                    visibility: tagged.visibility,
+                });
+        }
+    }
+
+    Ok(Dump {
+        version: DUMP_VERSION,
+        users: users
+            .into_iter()
+            .map(|user| User {
+                user_id: user.user_id,
+                username: user.username,
+                allowed: user.allowed,
+            })
+            .collect(),
+        stickers: stickers
+            .into_iter()
+            .map(|sticker| Sticker {
+                tags: tags_for_sticker_id.remove(&sticker.id).unwrap_or_default(),
+                file_unique_id: sticker.file_unique_id,
+                file_id: sticker.file_id,
+                set_name: sticker.set_name,
+                popularity: sticker.popularity,
+                emoji: sticker.emoji,
+                indexed_at: sticker.indexed_at,
+                kind: sticker.kind,
+            })
+            .collect(),
+    })
+}
+
+/// Number of new entries inserted by an import
+#[derive(Debug, Default)]
+pub struct ImportStats {
+    pub users: u64,
+    pub stickers: u64,
+    pub tags: u64,
+}
+
+/// Merges the dump into the database.
+///
+/// Existing users, stickers and tags are left untouched, so importing the same dump twice is a
+/// no-op. In particular, the permissions of existing users are never changed by an import.
+pub async fn import(db: &impl ConnectionTrait, dump: Dump) -> Result<ImportStats, DbErr> {
+    let mut stats = ImportStats::default();
+
+    for users in dump.users.chunks(CHUNK_SIZE) {
+        stats.users +=
+            model::user::Entity::insert_many(users.iter().map(|user| model::user::ActiveModel {
+                user_id: Set(user.user_id),
+                username: Set(user.username.clone()),
+                allowed: Set(user.allowed),
+                ..Default::default()
+            }))
+            .on_conflict(
+                OnConflict::column(model::user::Column::UserId)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
+    }
+
+    for stickers in dump.stickers.chunks(CHUNK_SIZE) {
+        stats.stickers += model::sticker::Entity::insert_many(stickers.iter().map(|sticker| {
+            model::sticker::ActiveModel {
+                file_unique_id: Set(sticker.file_unique_id.clone()),
+                file_id: Set(sticker.file_id.clone()),
+                set_name: Set(sticker.set_name.clone()),
+                popularity: Set(sticker.popularity),
+                emoji: Set(sticker.emoji.clone()),
+                indexed_at: Set(sticker.indexed_at),
+                kind: Set(sticker.kind.clone()),
+                ..Default::default()
+            }
+        }))
+        .on_conflict(
+            OnConflict::column(model::sticker::Column::FileUniqueId)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    }
+
+    // resolve the ids of the rows, whether they're newly inserted or not
+    let tagger_id_for_user_id: HashMap<i64, i32> = model::user::Entity::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|user| (user.user_id, user.id))
+        .collect();
+    let mut sticker_id_for_file_unique_id: HashMap<String, i32> = HashMap::new();
+    for stickers in dump.stickers.chunks(CHUNK_SIZE) {
+        let file_unique_ids = stickers
+            .iter()
+            .map(|sticker| sticker.file_unique_id.clone())
+            .collect_vec();
+        let rows = model::sticker::Entity::find()
+            .filter(model::sticker::Column::FileUniqueId.is_in(file_unique_ids))
+            .all(db)
+            .await?;
+        sticker_id_for_file_unique_id.extend(
+            rows.into_iter()
+                .map(|sticker| (sticker.file_unique_id, sticker.id)),
+        );
+    }
+
+    let tagged_stickers = dump
+        .stickers
+        .iter()
+        .flat_map(|sticker| sticker.tags.iter().map(move |tag| (sticker, tag)))
+        .filter_map(|(sticker, tag)| {
+            let sticker_id = *sticker_id_for_file_unique_id.get(&sticker.file_unique_id)?;
+            let tagger_id = *tagger_id_for_user_id.get(&tag.tagger_user_id)?;
+            let tag_language = tag
+                .language
+                .clone()
+                .or_else(|| language::detect(&tag.tag).map(str::to_owned));
+            Some(model::tagged_sticker::ActiveModel {
+                tag: Set(tag.tag.clone()),
+                sticker_id: Set(sticker_id),
+                tagger_id: Set(tagger_id),
+                ts: Set(tag.ts),
+                approved: Set(tag.approved),
+                namespace: Set(query::tag_namespace(&tag.tag)),
+                romanized: Set(language::romanize(&tag.tag, tag_language.as_deref())),
+                language: Set(tag_language),
+                chat_id: Set(tag.chat_id),
+                visibility: Set(tag.visibility.clone()),
+                scope: Set(model::tagged_sticker::scope(
+                    tag.chat_id,
+                    &tag.visibility,
+                    tagger_id,
+                )),
+                ..Default::default()
+            })
+        })
+        .collect_vec();
+    for tagged_stickers in tagged_stickers.chunks(CHUNK_SIZE) {
+        stats.tags += model::tagged_sticker::Entity::insert_many(tagged_stickers.to_vec())
+            .on_conflict(
+                OnConflict::columns([
+                    model::tagged_sticker::Column::StickerId,
+                    model::tagged_sticker::Column::Tag,
+                    model::tagged_sticker::Column::Scope,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
+    }
+
+    Ok(stats)
+}
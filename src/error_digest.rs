@@ -0,0 +1,120 @@
+//! Digest of the errors of the handlers for the admin chat
+//!
+//! Errors that aren't the fault of users would otherwise only show up in the logs, where nobody
+//! notices them. The handlers report them to the [`ErrorDigest`] of the data store, which counts
+//! them by kind and keeps the latest one, and the admin chat is sent what was collected once an
+//! hour at most, e.g. "12 database errors in the last hour, latest: ...".
+
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fmt::Display,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use itertools::Itertools;
+use teloxide::prelude::*;
+use tracing::{debug, error};
+
+use crate::{Bot, DataStore};
+
+/// How often the collected errors are sent, which the digest says it covers
+const DIGEST_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Longest description of the latest error in a digest, in characters
+const LATEST_ERROR_MAX_LEN: usize = 500;
+
+/// Errors collected since the last digest
+#[derive(Default)]
+pub struct ErrorDigest {
+    errors: Mutex<Errors>,
+}
+
+#[derive(Default)]
+struct Errors {
+    counts: HashMap<&'static str, u64>,
+    latest: Option<String>,
+}
+
+impl ErrorDigest {
+    /// Counts an error of the given kind, e.g. `database`
+    pub fn record(&self, kind: &'static str, error: &dyn Display) {
+        let mut errors = self.errors.lock().unwrap();
+        *errors.counts.entry(kind).or_default() += 1;
+        errors.latest = Some(error.to_string());
+    }
+
+    /// Describes the errors collected since the last call, if there were any, and forgets them
+    pub fn take(&self) -> Option<String> {
+        let errors = std::mem::take(&mut *self.errors.lock().unwrap());
+        let latest = errors.latest?;
+
+        // the most common kinds of errors come first
+        let counts = errors
+            .counts
+            .into_iter()
+            .sorted_by_key(|&(kind, count)| (Reverse(count), kind))
+            .map(|(kind, count)| match count {
+                1 => format!("1 {kind} error"),
+                count => format!("{count} {kind} errors"),
+            })
+            .join(", ");
+        let latest = match latest.char_indices().nth(LATEST_ERROR_MAX_LEN) {
+            Some((end, _)) => format!("{}…", &latest[..end]),
+            None => latest,
+        };
+        Some(format!("{counts} in the last hour, latest: {latest}"))
+    }
+}
+
+/// Sends the collected errors to the admin chat in a background task, once an interval at most
+pub fn spawn(bot: Bot, store: Arc<DataStore>, chat_id: ChatId) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(DIGEST_INTERVAL);
+        // the first tick completes right away, when there's nothing to send yet
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let digest = match store.errors.take() {
+                Some(digest) => digest,
+                None => {
+                    debug!("No errors to send a digest of");
+                    continue;
+                }
+            };
+            if let Err(e) = bot.send_message(chat_id, digest).send().await {
+                error!("Failed to send the error digest: {e}");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digest_counts_errors_by_kind_until_taken() {
+        let digest = ErrorDigest::default();
+        assert_eq!(digest.take(), None);
+
+        digest.record("Telegram", &"request timed out");
+        digest.record("database", &"connection reset");
+        digest.record("database", &"connection refused");
+        assert_eq!(
+            digest.take().as_deref(),
+            Some(
+                "2 database errors, 1 Telegram error in the last hour, latest: connection refused"
+            )
+        );
+        assert_eq!(digest.take(), None);
+    }
+
+    #[test]
+    fn digest_shortens_long_errors() {
+        let digest = ErrorDigest::default();
+        digest.record("other", &"錯".repeat(LATEST_ERROR_MAX_LEN + 1));
+        let text = digest.take().unwrap();
+        assert!(text.ends_with(&format!("{}…", "錯".repeat(LATEST_ERROR_MAX_LEN))));
+    }
+}
@@ -0,0 +1,65 @@
+//! HTTP health check endpoint for container orchestrators
+//!
+//! `GET /healthz` responds with 200 if the database is reachable and Telegram has been in contact
+//! recently, or else 503 with the reason.
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use axum::{http::StatusCode, routing::get, Router};
+use teloxide::prelude::*;
+use tracing::{error, info};
+
+use crate::{Bot, DataStore};
+
+/// Telegram is considered silent if no update has been received for this long
+const MAX_TELEGRAM_SILENCE: Duration = Duration::from_secs(10 * 60);
+const TELEGRAM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serves the health check endpoint on `port` in a background task
+pub fn spawn(bot: Bot, store: Arc<DataStore>, port: u16) {
+    let app = Router::new().route(
+        "/healthz",
+        get(move || {
+            let (bot, store) = (bot.clone(), store.clone());
+            async move {
+                match check(&bot, &store).await {
+                    Ok(()) => (StatusCode::OK, "ok".to_owned()),
+                    Err(reason) => {
+                        error!("Health check failed: {reason}");
+                        (StatusCode::SERVICE_UNAVAILABLE, reason)
+                    }
+                }
+            }
+        }),
+    );
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    tokio::spawn(async move {
+        info!("Serving health checks on {addr}/healthz");
+        if let Err(e) = axum::Server::bind(&addr)
+            .serve(app.into_make_service())
+            .await
+        {
+            error!("Health check server error: {e}");
+        }
+    });
+}
+
+async fn check(bot: &Bot, store: &DataStore) -> Result<(), String> {
+    store
+        .db
+        .ping()
+        .await
+        .map_err(|e| format!("database unreachable: {e}"))?;
+
+    // a quiet bot doesn't receive updates, so ask Telegram directly before reporting failure
+    if store.telegram_silence() > MAX_TELEGRAM_SILENCE {
+        tokio::time::timeout(TELEGRAM_TIMEOUT, bot.get_me().send())
+            .await
+            .map_err(|_| "telegram timed out".to_owned())?
+            .map_err(|e| format!("telegram unreachable: {e}"))?;
+        store.record_telegram_contact();
+    }
+
+    Ok(())
+}
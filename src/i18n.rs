@@ -0,0 +1,91 @@
+//! Translation of user-facing strings
+//!
+//! The English strings in [`crate::strings`] double as message ids, and so do the descriptions of
+//! the commands. A translation is a JSON object mapping them to the translated strings, in a file
+//! named after the language code reported by Telegram (e.g. `zh-hant.json`). The translations in
+//! `locales/` are built in, and more can be added, or the built-in ones overridden, by putting
+//! files in `LOCALES_DIR`.
+
+use std::{collections::HashMap, fs, path::Path, sync::OnceLock};
+
+use tracing::info;
+
+type Translation = HashMap<String, String>;
+
+static TRANSLATIONS: OnceLock<HashMap<String, Translation>> = OnceLock::new();
+
+const BUILTIN_TRANSLATIONS: &[(&str, &str)] =
+    &[("zh-hant", include_str!("../locales/zh-hant.json"))];
+
+/// Loads the built-in translations, and the ones in `dir` if given; must be called once before
+/// any translation happens, or else everything stays in English
+pub fn init(dir: Option<&Path>) {
+    let mut translations: HashMap<String, Translation> = BUILTIN_TRANSLATIONS
+        .iter()
+        .map(|(language_code, json)| {
+            let translation = serde_json::from_str(json).unwrap_or_else(|e| {
+                panic!("built-in {language_code} translation to be valid: {e}")
+            });
+            (language_code.to_string(), translation)
+        })
+        .collect();
+
+    if let Some(dir) = dir {
+        let entries = fs::read_dir(dir).expect("locales_dir to be a readable directory");
+        for path in entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+        {
+            let language_code = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) if path.extension().map_or(false, |ext| ext == "json") => {
+                    stem.to_lowercase()
+                }
+                _ => continue,
+            };
+            let json = fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("{path:?} to be readable: {e}"));
+            let translation: Translation = serde_json::from_str(&json)
+                .unwrap_or_else(|e| panic!("{path:?} to be a valid translation: {e}"));
+
+            info!(
+                "Loaded {n} translated strings for {language_code}",
+                n = translation.len()
+            );
+            translations
+                .entry(language_code)
+                .or_default()
+                .extend(translation);
+        }
+    }
+
+    if TRANSLATIONS.set(translations).is_err() {
+        panic!("translations to be initialized only once");
+    }
+}
+
+/// Language codes of the loaded translations, sorted
+pub fn languages() -> Vec<&'static str> {
+    let mut languages = match TRANSLATIONS.get() {
+        Some(translations) => translations.keys().map(String::as_str).collect::<Vec<_>>(),
+        None => vec![],
+    };
+    languages.sort_unstable();
+    languages
+}
+
+/// Translates `text` into the language, falling back to the language without its region or
+/// script (e.g. `pt` for `pt-br`), and then to the untranslated `text`
+pub fn translate<'a>(language_code: Option<&str>, text: &'a str) -> &'a str {
+    let (translations, language_code) = match (TRANSLATIONS.get(), language_code) {
+        (Some(translations), Some(language_code)) => (translations, language_code.to_lowercase()),
+        _ => return text,
+    };
+    let primary_language = language_code.split('-').next().unwrap_or_default();
+
+    [language_code.as_str(), primary_language]
+        .iter()
+        .filter_map(|language| translations.get(*language))
+        .find_map(|translation| translation.get(text))
+        .map(|translated| translated.as_str())
+        .unwrap_or(text)
+}
@@ -0,0 +1,210 @@
+//! Languages of tags
+//!
+//! Taggers may give the language of a tag with a prefix of its two-letter ISO 639-1 code, e.g.
+//! `/tag zh:哭 en:cry`. Otherwise the language is detected from the script of the tag where the
+//! script tells, e.g. kana for Japanese, and left unknown where it doesn't, e.g. for latin letters.
+//! Searches prefer the stickers whose matching tags are in the language of the searcher.
+//!
+//! Builds with the `pinyin` feature also store the pinyin of Chinese tags, so that they can be
+//! found by typing it.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::query;
+
+/// Whether `code` looks like a two-letter ISO 639-1 language code, e.g. `en`
+pub fn is_language_code(code: &str) -> bool {
+    code.len() == 2 && code.chars().all(|c| c.is_ascii_lowercase())
+}
+
+/// Language of a Telegram user, from the IETF language tag of their client, e.g. `zh` for
+/// `zh-hans`
+pub fn user_language(language_code: Option<&str>) -> Option<String> {
+    let language = language_code?.split('-').next()?.to_ascii_lowercase();
+    is_language_code(&language).then_some(language)
+}
+
+/// Splits the tags given by a tagger into the tags and their languages, in order and without
+/// duplicates
+pub fn parse_tags(text: &str) -> Vec<(String, Option<String>)> {
+    query::split_terms(text)
+        .into_iter()
+        .filter_map(|term| match term.split_once(':') {
+            Some((language, tag)) if is_language_code(language) => {
+                (tag.is_empty() == false).then(|| (tag.to_owned(), Some(language.to_owned())))
+            }
+            _ => {
+                let language = detect(&term).map(str::to_owned);
+                Some((term, language))
+            }
+        })
+        .unique_by(|(tag, _)| tag.clone())
+        .collect()
+}
+
+/// Languages of the tags split by [`parse_tags`], for the tags whose language is known
+pub fn tag_languages(tags: &[(String, Option<String>)]) -> HashMap<&str, &str> {
+    tags.iter()
+        .filter_map(|(tag, language)| Some((tag.as_str(), language.as_deref()?)))
+        .collect()
+}
+
+/// Language of a tag as far as its script tells
+pub fn detect(tag: &str) -> Option<&'static str> {
+    // kanji are used in Japanese as well, so kana decide first
+    if tag.chars().any(is_kana) {
+        Some("ja")
+    } else if tag.chars().any(is_hangul) {
+        Some("ko")
+    } else if tag.chars().any(is_han) {
+        Some("zh")
+    } else if tag.chars().any(is_thai) {
+        Some("th")
+    } else {
+        None
+    }
+}
+
+/// Pinyin of a tag in Chinese without tones and spaces between syllables, e.g. `kuku` for `哭哭`;
+/// the characters other than Chinese ones are kept as they are
+#[cfg(feature = "pinyin")]
+pub fn romanize(tag: &str, language: Option<&str>) -> Option<String> {
+    use pinyin::ToPinyin;
+
+    // kanji are read differently in Japanese
+    if language != Some("zh") || tag.chars().any(is_han) == false {
+        return None;
+    }
+    let romanized = tag
+        .chars()
+        .map(|c| match c.to_pinyin() {
+            Some(pinyin) => pinyin.plain().to_owned(),
+            None => c.to_lowercase().to_string(),
+        })
+        .collect();
+    Some(romanized)
+}
+
+/// Tags aren't romanized without the `pinyin` feature
+#[cfg(not(feature = "pinyin"))]
+pub fn romanize(_tag: &str, _language: Option<&str>) -> Option<String> {
+    None
+}
+
+/// Whether `word` is too common in the given language to be worth searching for, e.g. `the`
+pub fn is_stop_word(word: &str, language: &str) -> bool {
+    let stop_words: &[&str] = match language {
+        "en" => &[
+            "a", "an", "and", "are", "as", "at", "be", "by", "for", "in", "is", "it", "of", "on",
+            "or", "the", "to", "with",
+        ],
+        "de" => &[
+            "der", "die", "das", "ein", "eine", "und", "oder", "ist", "zu", "von", "mit", "im",
+        ],
+        "es" => &[
+            "el", "la", "los", "las", "un", "una", "y", "o", "de", "en", "es", "que",
+        ],
+        "fr" => &[
+            "le", "la", "les", "un", "une", "et", "ou", "de", "des", "du", "en", "est",
+        ],
+        "zh" => &[
+            "的", "了", "是", "在", "和", "與", "与", "也", "就", "都", "著", "着", "嗎", "吗",
+            "呢",
+        ],
+        "ja" => &[
+            "の", "は", "が", "を", "に", "で", "と", "も", "へ", "や", "です", "ます",
+        ],
+        "ko" => &[
+            "은", "는", "이", "가", "을", "를", "의", "에", "와", "과", "도",
+        ],
+        _ => &[],
+    };
+    stop_words.contains(&word.to_lowercase().as_str())
+}
+
+/// Whether `c` is a Chinese, Japanese or Korean character, whose text is searched by n-grams
+/// rather than by words
+pub fn is_cjk(c: char) -> bool {
+    is_han(c) || is_kana(c) || is_hangul(c)
+}
+
+fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3040}'..='\u{30ff}' | '\u{31f0}'..='\u{31ff}' | '\u{ff66}'..='\u{ff9f}')
+}
+
+fn is_hangul(c: char) -> bool {
+    matches!(c, '\u{1100}'..='\u{11ff}' | '\u{3130}'..='\u{318f}' | '\u{ac00}'..='\u{d7a3}')
+}
+
+fn is_han(c: char) -> bool {
+    matches!(c, '\u{3400}'..='\u{4dbf}' | '\u{4e00}'..='\u{9fff}' | '\u{f900}'..='\u{faff}')
+}
+
+fn is_thai(c: char) -> bool {
+    matches!(c, '\u{0e00}'..='\u{0e7f}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(tag: &str, language: Option<&str>) -> (String, Option<String>) {
+        (tag.to_owned(), language.map(str::to_owned))
+    }
+
+    #[test]
+    fn parse_tags_takes_prefixed_or_detected_languages() {
+        assert_eq!(
+            parse_tags("zh:哭 en:cry smile 笑う"),
+            [
+                tag("哭", Some("zh")),
+                tag("cry", Some("en")),
+                tag("smile", None),
+                tag("笑う", Some("ja")),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_tags_drops_duplicates_and_empty_tags() {
+        assert_eq!(parse_tags("cry en:cry en:"), [tag("cry", None)]);
+        assert_eq!(parse_tags("char:miku"), [tag("char:miku", None)]);
+    }
+
+    #[test]
+    fn tag_languages_skips_unknown_languages() {
+        let tags = parse_tags("zh:哭 smile");
+        let languages = tag_languages(&tags);
+        assert_eq!(languages.get("哭"), Some(&"zh"));
+        assert_eq!(languages.get("smile"), None);
+    }
+
+    #[test]
+    fn user_language_takes_primary_subtag() {
+        assert_eq!(user_language(Some("zh-hans")).as_deref(), Some("zh"));
+        assert_eq!(user_language(Some("EN")).as_deref(), Some("en"));
+        assert_eq!(user_language(Some("yue")), None);
+        assert_eq!(user_language(None), None);
+    }
+
+    #[test]
+    fn detect_tells_languages_by_script() {
+        assert_eq!(detect("ねこ"), Some("ja"));
+        assert_eq!(detect("猫になる"), Some("ja"));
+        assert_eq!(detect("고양이"), Some("ko"));
+        assert_eq!(detect("貓"), Some("zh"));
+        assert_eq!(detect("แมว"), Some("th"));
+        assert_eq!(detect("cat"), None);
+    }
+
+    #[test]
+    fn stop_words_depend_on_language() {
+        assert!(is_stop_word("The", "en"));
+        assert!(is_stop_word("die", "de"));
+        assert!(is_stop_word("die", "en") == false);
+        assert!(is_stop_word("的", "zh"));
+        assert!(is_stop_word("cat", "xx") == false);
+    }
+}
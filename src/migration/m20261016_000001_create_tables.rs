@@ -0,0 +1,136 @@
+//! Initial schema, identical to the tables previously created from the entity definitions
+//!
+//! The tables are created only if they don't exist, so that deployments created before
+//! migrations were introduced are picked up as-is.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TaggedSticker::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TaggedSticker::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(TaggedSticker::Tag).text().not_null())
+                    .col(
+                        ColumnDef::new(TaggedSticker::StickerId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TaggedSticker::TaggerId).integer().not_null())
+                    .col(
+                        ColumnDef::new(TaggedSticker::Ts)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Sticker::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Sticker::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Sticker::FileUniqueId)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Sticker::FileId).string().not_null())
+                    .col(ColumnDef::new(Sticker::SetName).string().not_null())
+                    .col(ColumnDef::new(Sticker::Popularity).big_integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(AllowedUser::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AllowedUser::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(AllowedUser::UserId)
+                            .big_integer()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(AllowedUser::Username).text().not_null())
+                    .col(ColumnDef::new(AllowedUser::Allowed).boolean().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AllowedUser::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Sticker::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(TaggedSticker::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum TaggedSticker {
+    Table,
+    Id,
+    Tag,
+    StickerId,
+    TaggerId,
+    Ts,
+}
+
+#[derive(DeriveIden)]
+enum Sticker {
+    Table,
+    Id,
+    FileUniqueId,
+    FileId,
+    SetName,
+    Popularity,
+}
+
+#[derive(DeriveIden)]
+enum AllowedUser {
+    Table,
+    Id,
+    UserId,
+    Username,
+    Allowed,
+}
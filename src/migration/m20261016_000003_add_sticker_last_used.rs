@@ -0,0 +1,53 @@
+//! Tracks when each sticker was last chosen, used to decay its popularity
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sticker::Table)
+                    .add_column(
+                        ColumnDef::new(Sticker::LastUsed)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // start decaying the popularity of existing stickers from now on
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Sticker::Table)
+                    .value(Sticker::LastUsed, Expr::current_timestamp())
+                    .and_where(Expr::col(Sticker::Popularity).gt(0))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sticker::Table)
+                    .drop_column(Sticker::LastUsed)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Sticker {
+    Table,
+    Popularity,
+    LastUsed,
+}
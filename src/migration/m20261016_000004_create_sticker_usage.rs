@@ -0,0 +1,56 @@
+//! Tracks the stickers chosen by each user, used to personalize the results
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(StickerUsage::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(StickerUsage::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(StickerUsage::StickerId).integer().not_null())
+                    .col(
+                        ColumnDef::new(StickerUsage::TimesChosen)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(StickerUsage::LastUsed)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(StickerUsage::UserId)
+                            .col(StickerUsage::StickerId),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(StickerUsage::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum StickerUsage {
+    Table,
+    UserId,
+    StickerId,
+    TimesChosen,
+    LastUsed,
+}
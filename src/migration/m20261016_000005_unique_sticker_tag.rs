@@ -0,0 +1,86 @@
+//! Prevents the same tag from being added to a sticker more than once
+//!
+//! MySQL can only index a prefix of a text column, so there tags sharing their first 255
+//! characters count as the same tag.
+
+use sea_orm_migration::{prelude::*, sea_orm::DatabaseBackend};
+
+const INDEX_NAME: &str = "idx-tagged_sticker-sticker_id-tag";
+// longest prefix of the tags indexed on MySQL, which keeps the key under its limit of 3072 bytes
+const TAG_PREFIX_LEN: u32 = 255;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // remove existing duplicates, keeping the earliest ones; MySQL can't select from the table
+        // it deletes from, unless the selection is materialized in a derived table first
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(TaggedSticker::Table)
+                    .and_where(
+                        Expr::col(TaggedSticker::Id).not_in_subquery(
+                            Query::select()
+                                .column(TaggedSticker::Id)
+                                .from_subquery(
+                                    Query::select()
+                                        .expr_as(
+                                            Expr::col(TaggedSticker::Id).min(),
+                                            TaggedSticker::Id,
+                                        )
+                                        .from(TaggedSticker::Table)
+                                        .group_by_columns([
+                                            TaggedSticker::StickerId,
+                                            TaggedSticker::Tag,
+                                        ])
+                                        .to_owned(),
+                                    Kept::Table,
+                                )
+                                .to_owned(),
+                        ),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut index = Index::create();
+        index
+            .name(INDEX_NAME)
+            .table(TaggedSticker::Table)
+            .col(TaggedSticker::StickerId)
+            .unique();
+        if manager.get_database_backend() == DatabaseBackend::MySql {
+            index.col((TaggedSticker::Tag, TAG_PREFIX_LEN));
+        } else {
+            index.col(TaggedSticker::Tag);
+        }
+        manager.create_index(index.to_owned()).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(INDEX_NAME)
+                    .table(TaggedSticker::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TaggedSticker {
+    Table,
+    Id,
+    StickerId,
+    Tag,
+}
+
+#[derive(DeriveIden)]
+enum Kept {
+    Table,
+}
@@ -0,0 +1,57 @@
+//! Records destructive and permission-changing actions
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLog::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuditLog::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(AuditLog::Ts)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(AuditLog::Action).string().not_null())
+                    .col(ColumnDef::new(AuditLog::ActorUserId).big_integer().null())
+                    .col(ColumnDef::new(AuditLog::ActorUsername).text().null())
+                    .col(ColumnDef::new(AuditLog::StickerId).integer().null())
+                    .col(ColumnDef::new(AuditLog::TargetUsername).text().null())
+                    .col(ColumnDef::new(AuditLog::Tags).text().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditLog::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuditLog {
+    Table,
+    Id,
+    Ts,
+    Action,
+    ActorUserId,
+    ActorUsername,
+    StickerId,
+    TargetUsername,
+    Tags,
+}
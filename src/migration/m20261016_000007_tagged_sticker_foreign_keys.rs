@@ -0,0 +1,212 @@
+//! Links the tags to their stickers and taggers, deleting the tags along with either of them
+//!
+//! SQLite can't add constraints to an existing table, so there the table is rebuilt instead.
+
+use sea_orm_migration::{prelude::*, sea_orm::DatabaseBackend};
+
+const STICKER_FOREIGN_KEY: &str = "fk-tagged_sticker-sticker_id";
+const TAGGER_FOREIGN_KEY: &str = "fk-tagged_sticker-tagger_id";
+const UNIQUE_INDEX_NAME: &str = "idx-tagged_sticker-sticker_id-tag";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // remove the tags left behind by deleted stickers or taggers, which violate the keys
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(TaggedSticker::Table)
+                    .cond_where(
+                        Cond::any()
+                            .add(
+                                Expr::col(TaggedSticker::StickerId).not_in_subquery(
+                                    Query::select()
+                                        .column(Sticker::Id)
+                                        .from(Sticker::Table)
+                                        .to_owned(),
+                                ),
+                            )
+                            .add(
+                                Expr::col(TaggedSticker::TaggerId).not_in_subquery(
+                                    Query::select()
+                                        .column(AllowedUser::Id)
+                                        .from(AllowedUser::Table)
+                                        .to_owned(),
+                                ),
+                            ),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        if manager.get_database_backend() == DatabaseBackend::Sqlite {
+            return rebuild_table(manager, true).await;
+        }
+
+        manager
+            .create_foreign_key(sticker_foreign_key().to_owned())
+            .await?;
+        manager
+            .create_foreign_key(tagger_foreign_key().to_owned())
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        if manager.get_database_backend() == DatabaseBackend::Sqlite {
+            return rebuild_table(manager, false).await;
+        }
+
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name(TAGGER_FOREIGN_KEY)
+                    .table(TaggedSticker::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_foreign_key(
+                ForeignKey::drop()
+                    .name(STICKER_FOREIGN_KEY)
+                    .table(TaggedSticker::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+fn sticker_foreign_key() -> ForeignKeyCreateStatement {
+    ForeignKey::create()
+        .name(STICKER_FOREIGN_KEY)
+        .from(TaggedSticker::Table, TaggedSticker::StickerId)
+        .to(Sticker::Table, Sticker::Id)
+        .on_delete(ForeignKeyAction::Cascade)
+        .to_owned()
+}
+
+fn tagger_foreign_key() -> ForeignKeyCreateStatement {
+    ForeignKey::create()
+        .name(TAGGER_FOREIGN_KEY)
+        .from(TaggedSticker::Table, TaggedSticker::TaggerId)
+        .to(AllowedUser::Table, AllowedUser::Id)
+        .on_delete(ForeignKeyAction::Cascade)
+        .to_owned()
+}
+
+/// Recreates the `tagged_sticker` table with or without the foreign keys, keeping its rows
+async fn rebuild_table(manager: &SchemaManager<'_>, foreign_keys: bool) -> Result<(), DbErr> {
+    let columns = [
+        TaggedSticker::Id,
+        TaggedSticker::Tag,
+        TaggedSticker::StickerId,
+        TaggedSticker::TaggerId,
+        TaggedSticker::Ts,
+    ];
+
+    let mut create = Table::create();
+    create
+        .table(TaggedStickerNew::Table)
+        .col(
+            ColumnDef::new(TaggedSticker::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(TaggedSticker::Tag).text().not_null())
+        .col(
+            ColumnDef::new(TaggedSticker::StickerId)
+                .integer()
+                .not_null(),
+        )
+        .col(ColumnDef::new(TaggedSticker::TaggerId).integer().not_null())
+        .col(
+            ColumnDef::new(TaggedSticker::Ts)
+                .timestamp_with_time_zone()
+                .not_null(),
+        );
+    if foreign_keys {
+        create
+            .foreign_key(
+                &mut sticker_foreign_key()
+                    .from_tbl(TaggedStickerNew::Table)
+                    .to_owned(),
+            )
+            .foreign_key(
+                &mut tagger_foreign_key()
+                    .from_tbl(TaggedStickerNew::Table)
+                    .to_owned(),
+            );
+    }
+    manager.create_table(create.to_owned()).await?;
+
+    manager
+        .exec_stmt(
+            Query::insert()
+                .into_table(TaggedStickerNew::Table)
+                .columns(columns)
+                .select_from(
+                    Query::select()
+                        .columns(columns)
+                        .from(TaggedSticker::Table)
+                        .to_owned(),
+                )
+                .map_err(|e| DbErr::Migration(e.to_string()))?
+                .to_owned(),
+        )
+        .await?;
+
+    manager
+        .drop_table(Table::drop().table(TaggedSticker::Table).to_owned())
+        .await?;
+    manager
+        .rename_table(
+            Table::rename()
+                .table(TaggedStickerNew::Table, TaggedSticker::Table)
+                .to_owned(),
+        )
+        .await?;
+
+    // the unique index was dropped along with the old table
+    manager
+        .create_index(
+            Index::create()
+                .name(UNIQUE_INDEX_NAME)
+                .table(TaggedSticker::Table)
+                .col(TaggedSticker::StickerId)
+                .col(TaggedSticker::Tag)
+                .unique()
+                .to_owned(),
+        )
+        .await
+}
+
+#[derive(DeriveIden, Clone, Copy)]
+enum TaggedSticker {
+    Table,
+    Id,
+    Tag,
+    StickerId,
+    TaggerId,
+    Ts,
+}
+
+#[derive(DeriveIden)]
+enum TaggedStickerNew {
+    Table,
+}
+
+#[derive(DeriveIden)]
+enum Sticker {
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum AllowedUser {
+    Table,
+    Id,
+}
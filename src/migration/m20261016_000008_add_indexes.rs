@@ -0,0 +1,88 @@
+//! Indexes on the columns filtered and sorted on by the inline query and command handlers
+//!
+//! `sticker.file_unique_id` is already indexed by its unique constraint, and
+//! `tagged_sticker.sticker_id` by the unique index on `(sticker_id, tag)`, of which it is the
+//! leading column. MySQL can only index a prefix of the tags, being text.
+
+use sea_orm_migration::{prelude::*, sea_orm::DatabaseBackend};
+
+const TAG_INDEX_NAME: &str = "idx-tagged_sticker-tag";
+const TAGGER_ID_INDEX_NAME: &str = "idx-tagged_sticker-tagger_id";
+const POPULARITY_INDEX_NAME: &str = "idx-sticker-popularity";
+// longest prefix of the tags indexed on MySQL
+const TAG_PREFIX_LEN: u32 = 255;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut tag_index = Index::create();
+        tag_index.name(TAG_INDEX_NAME).table(TaggedSticker::Table);
+        if manager.get_database_backend() == DatabaseBackend::MySql {
+            tag_index.col((TaggedSticker::Tag, TAG_PREFIX_LEN));
+        } else {
+            tag_index.col(TaggedSticker::Tag);
+        }
+        manager.create_index(tag_index.to_owned()).await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name(TAGGER_ID_INDEX_NAME)
+                    .table(TaggedSticker::Table)
+                    .col(TaggedSticker::TaggerId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name(POPULARITY_INDEX_NAME)
+                    .table(Sticker::Table)
+                    .col(Sticker::Popularity)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(POPULARITY_INDEX_NAME)
+                    .table(Sticker::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(TAGGER_ID_INDEX_NAME)
+                    .table(TaggedSticker::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(TAG_INDEX_NAME)
+                    .table(TaggedSticker::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TaggedSticker {
+    Table,
+    Tag,
+    TaggerId,
+}
+
+#[derive(DeriveIden)]
+enum Sticker {
+    Table,
+    Popularity,
+}
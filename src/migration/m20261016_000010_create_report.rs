@@ -0,0 +1,77 @@
+//! Records the reports of bad tags filed with /report
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Report::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Report::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Report::StickerId).integer().not_null())
+                    .col(
+                        ColumnDef::new(Report::ReporterUserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Report::ReporterUsername).text().null())
+                    .col(ColumnDef::new(Report::Reason).text().null())
+                    .col(
+                        ColumnDef::new(Report::Ts)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Report::Resolved)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-report-sticker_id")
+                            .from(Report::Table, Report::StickerId)
+                            .to(Sticker::Table, Sticker::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Report::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Report {
+    Table,
+    Id,
+    StickerId,
+    ReporterUserId,
+    ReporterUsername,
+    Reason,
+    Ts,
+    Resolved,
+}
+
+#[derive(DeriveIden)]
+enum Sticker {
+    Table,
+    Id,
+}
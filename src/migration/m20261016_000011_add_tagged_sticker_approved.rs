@@ -0,0 +1,44 @@
+//! Marks whether tags have been approved, so that the tags of new taggers can await review
+//!
+//! Existing tags are considered approved.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaggedSticker::Table)
+                    .add_column(
+                        ColumnDef::new(TaggedSticker::Approved)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaggedSticker::Table)
+                    .drop_column(TaggedSticker::Approved)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TaggedSticker {
+    Table,
+    Approved,
+}
@@ -0,0 +1,76 @@
+//! Records when stickers were indexed
+//!
+//! Existing stickers are assumed to have been indexed when they were first tagged.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sticker::Table)
+                    .add_column(
+                        ColumnDef::new(Sticker::IndexedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Sticker::Table)
+                    .value(
+                        Sticker::IndexedAt,
+                        SimpleExpr::SubQuery(
+                            None,
+                            Box::new(
+                                Query::select()
+                                    .expr(Expr::col(TaggedSticker::Ts).min())
+                                    .from(TaggedSticker::Table)
+                                    .and_where(
+                                        Expr::col((TaggedSticker::Table, TaggedSticker::StickerId))
+                                            .equals((Sticker::Table, Sticker::Id)),
+                                    )
+                                    .to_owned()
+                                    .into_sub_query_statement(),
+                            ),
+                        ),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sticker::Table)
+                    .drop_column(Sticker::IndexedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Sticker {
+    Table,
+    Id,
+    IndexedAt,
+}
+
+#[derive(DeriveIden)]
+enum TaggedSticker {
+    Table,
+    StickerId,
+    Ts,
+}
@@ -0,0 +1,73 @@
+//! Records the format, dimensions and thumbnail of stickers
+//!
+//! The metadata of existing stickers is unknown until their sets are refreshed with /refreshset.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite can only add a single column at a time
+        for column in [
+            ColumnDef::new(Sticker::IsAnimated)
+                .boolean()
+                .not_null()
+                .default(false)
+                .to_owned(),
+            ColumnDef::new(Sticker::IsVideo)
+                .boolean()
+                .not_null()
+                .default(false)
+                .to_owned(),
+            ColumnDef::new(Sticker::Width).integer().null().to_owned(),
+            ColumnDef::new(Sticker::Height).integer().null().to_owned(),
+            ColumnDef::new(Sticker::ThumbFileId)
+                .text()
+                .null()
+                .to_owned(),
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Sticker::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Sticker::ThumbFileId,
+            Sticker::Height,
+            Sticker::Width,
+            Sticker::IsVideo,
+            Sticker::IsAnimated,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Sticker::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Sticker {
+    Table,
+    IsAnimated,
+    IsVideo,
+    Width,
+    Height,
+    ThumbFileId,
+}
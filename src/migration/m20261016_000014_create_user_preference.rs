@@ -0,0 +1,40 @@
+//! Stores the preferences of users, who are not necessarily taggers
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserPreference::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserPreference::UserId)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(UserPreference::StickerType).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserPreference::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserPreference {
+    Table,
+    UserId,
+    StickerType,
+}
@@ -0,0 +1,91 @@
+//! Logs every sticker chosen from inline query results, along with the query it was found with
+
+use sea_orm_migration::prelude::*;
+
+const TS_INDEX_NAME: &str = "idx-chosen_result-ts";
+const STICKER_ID_INDEX_NAME: &str = "idx-chosen_result-sticker_id";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ChosenResult::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ChosenResult::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ChosenResult::StickerId).integer().not_null())
+                    .col(
+                        ColumnDef::new(ChosenResult::UserId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ChosenResult::Query).text().not_null())
+                    .col(
+                        ColumnDef::new(ChosenResult::Ts)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-chosen_result-sticker_id")
+                            .from(ChosenResult::Table, ChosenResult::StickerId)
+                            .to(Sticker::Table, Sticker::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // the log is aggregated over recent time spans, and per sticker
+        manager
+            .create_index(
+                Index::create()
+                    .name(TS_INDEX_NAME)
+                    .table(ChosenResult::Table)
+                    .col(ChosenResult::Ts)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name(STICKER_ID_INDEX_NAME)
+                    .table(ChosenResult::Table)
+                    .col(ChosenResult::StickerId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ChosenResult::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ChosenResult {
+    Table,
+    Id,
+    StickerId,
+    UserId,
+    Query,
+    Ts,
+}
+
+#[derive(DeriveIden)]
+enum Sticker {
+    Table,
+    Id,
+}
@@ -0,0 +1,53 @@
+//! Counts the inline queries that returned no results, keyed by their search terms
+
+use sea_orm_migration::{prelude::*, sea_orm::DatabaseBackend};
+
+// longest prefix of the queries keyed on MySQL, which inline queries rarely exceed
+const QUERY_PREFIX_LEN: u32 = 255;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut query = ColumnDef::new(MissedQuery::Query);
+        query.text().not_null();
+        let mut table = Table::create();
+        table.table(MissedQuery::Table).if_not_exists();
+        // MySQL can only key a prefix of a text column
+        if manager.get_database_backend() == DatabaseBackend::MySql {
+            table
+                .col(&mut query)
+                .primary_key(Index::create().col((MissedQuery::Query, QUERY_PREFIX_LEN)));
+        } else {
+            table.col(query.primary_key());
+        }
+        table
+            .col(
+                ColumnDef::new(MissedQuery::TimesMissed)
+                    .big_integer()
+                    .not_null(),
+            )
+            .col(
+                ColumnDef::new(MissedQuery::LastMissed)
+                    .timestamp_with_time_zone()
+                    .not_null(),
+            );
+        manager.create_table(table.to_owned()).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MissedQuery::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MissedQuery {
+    Table,
+    Query,
+    TimesMissed,
+    LastMissed,
+}
@@ -0,0 +1,45 @@
+//! Records the ids of the updates handled recently, so that several instances of the bot sharing
+//! the database don't handle the same update twice
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProcessedUpdate::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ProcessedUpdate::UpdateId)
+                            .big_integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ProcessedUpdate::Ts)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ProcessedUpdate::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ProcessedUpdate {
+    Table,
+    UpdateId,
+    Ts,
+}
@@ -0,0 +1,71 @@
+//! Lets taggers propagate tags to every sticker in a set
+//!
+//! Propagated tags point to the tag they were propagated from, so that they can be told apart
+//! from the tags added by hand and revoked in bulk.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaggedSticker::Table)
+                    .add_column(
+                        ColumnDef::new(TaggedSticker::InheritedFrom)
+                            .integer()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AllowedUser::Table)
+                    .add_column(
+                        ColumnDef::new(AllowedUser::PropagateTags)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AllowedUser::Table)
+                    .drop_column(AllowedUser::PropagateTags)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaggedSticker::Table)
+                    .drop_column(TaggedSticker::InheritedFrom)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TaggedSticker {
+    Table,
+    InheritedFrom,
+}
+
+#[derive(DeriveIden)]
+enum AllowedUser {
+    Table,
+    PropagateTags,
+}
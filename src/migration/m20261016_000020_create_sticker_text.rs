@@ -0,0 +1,59 @@
+//! Stores the text recognized in the stickers, so that they can be found by it
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(StickerText::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(StickerText::StickerId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(StickerText::Text).text().not_null())
+                    .col(
+                        ColumnDef::new(StickerText::Ts)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-sticker_text-sticker_id")
+                            .from(StickerText::Table, StickerText::StickerId)
+                            .to(Sticker::Table, Sticker::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(StickerText::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum StickerText {
+    Table,
+    StickerId,
+    Text,
+    Ts,
+}
+
+#[derive(DeriveIden)]
+enum Sticker {
+    Table,
+    Id,
+}
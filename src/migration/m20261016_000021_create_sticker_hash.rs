@@ -0,0 +1,59 @@
+//! Stores perceptual hashes of the stickers, so that stickers looking alike can be found
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(StickerHash::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(StickerHash::StickerId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(StickerHash::Hash).big_integer().null())
+                    .col(
+                        ColumnDef::new(StickerHash::Ts)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-sticker_hash-sticker_id")
+                            .from(StickerHash::Table, StickerHash::StickerId)
+                            .to(Sticker::Table, Sticker::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(StickerHash::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum StickerHash {
+    Table,
+    StickerId,
+    Hash,
+    Ts,
+}
+
+#[derive(DeriveIden)]
+enum Sticker {
+    Table,
+    Id,
+}
@@ -0,0 +1,59 @@
+//! Stores the stickers each user has added to their favorites with /fav
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Favorite::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Favorite::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(Favorite::StickerId).integer().not_null())
+                    .col(
+                        ColumnDef::new(Favorite::Ts)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(Favorite::UserId)
+                            .col(Favorite::StickerId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-favorite-sticker_id")
+                            .from(Favorite::Table, Favorite::StickerId)
+                            .to(Sticker::Table, Sticker::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Favorite::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Favorite {
+    Table,
+    UserId,
+    StickerId,
+    Ts,
+}
+
+#[derive(DeriveIden)]
+enum Sticker {
+    Table,
+    Id,
+}
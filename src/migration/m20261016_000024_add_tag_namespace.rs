@@ -0,0 +1,85 @@
+//! Stores the namespace of tags of the form `namespace:value` along with them, e.g. `char` for
+//! `char:miku`, so that searches can filter on it
+//!
+//! The namespaces of the existing tags are filled in with the same rule as the new ones.
+
+use sea_orm_migration::{prelude::*, sea_orm::ConnectionTrait};
+
+use crate::query;
+
+const NAMESPACE_INDEX_NAME: &str = "idx-tagged_sticker-namespace";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaggedSticker::Table)
+                    .add_column(ColumnDef::new(TaggedSticker::Namespace).string().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name(NAMESPACE_INDEX_NAME)
+                    .table(TaggedSticker::Table)
+                    .col(TaggedSticker::Namespace)
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        let backend = db.get_database_backend();
+        let select = Query::select()
+            .columns([TaggedSticker::Id, TaggedSticker::Tag])
+            .from(TaggedSticker::Table)
+            .and_where(Expr::col(TaggedSticker::Tag).like("%:%"))
+            .to_owned();
+        for row in db.query_all(backend.build(&select)).await? {
+            let id: i32 = row.try_get("", "id")?;
+            let tag: String = row.try_get("", "tag")?;
+            if let Some(namespace) = query::tag_namespace(&tag) {
+                let update = Query::update()
+                    .table(TaggedSticker::Table)
+                    .value(TaggedSticker::Namespace, namespace)
+                    .and_where(Expr::col(TaggedSticker::Id).eq(id))
+                    .to_owned();
+                db.execute(backend.build(&update)).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(NAMESPACE_INDEX_NAME)
+                    .table(TaggedSticker::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaggedSticker::Table)
+                    .drop_column(TaggedSticker::Namespace)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TaggedSticker {
+    Table,
+    Id,
+    Tag,
+    Namespace,
+}
@@ -0,0 +1,77 @@
+//! Stores the language of tags along with them, so that searches can prefer the tags in the
+//! language of the searcher
+//!
+//! The languages of the existing tags are detected from their scripts. Tags that were stored with
+//! a language code as their namespace, e.g. `zh:哭`, take it as their language instead.
+
+use sea_orm_migration::{prelude::*, sea_orm::ConnectionTrait};
+
+use crate::language;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaggedSticker::Table)
+                    .add_column(ColumnDef::new(TaggedSticker::Language).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        let backend = db.get_database_backend();
+        let select = Query::select()
+            .columns([TaggedSticker::Id, TaggedSticker::Tag])
+            .from(TaggedSticker::Table)
+            .to_owned();
+        for row in db.query_all(backend.build(&select)).await? {
+            let id: i32 = row.try_get("", "id")?;
+            let tag: String = row.try_get("", "tag")?;
+            let (code, has_namespace) = match tag.split_once(':') {
+                Some((code, _)) if language::is_language_code(code) => (Some(code), true),
+                _ => (language::detect(&tag), false),
+            };
+            let code = match code {
+                Some(code) => code,
+                None => continue,
+            };
+
+            let mut update = Query::update();
+            update
+                .table(TaggedSticker::Table)
+                .value(TaggedSticker::Language, code)
+                .and_where(Expr::col(TaggedSticker::Id).eq(id));
+            if has_namespace {
+                update.value(TaggedSticker::Namespace, Option::<String>::None);
+            }
+            db.execute(backend.build(&update)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaggedSticker::Table)
+                    .drop_column(TaggedSticker::Language)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TaggedSticker {
+    Table,
+    Id,
+    Tag,
+    Namespace,
+    Language,
+}
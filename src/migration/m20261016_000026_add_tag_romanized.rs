@@ -0,0 +1,41 @@
+//! Stores the romanization of Chinese tags along with them, e.g. `ku` for `哭`, so that they can
+//! be searched for in latin letters
+//!
+//! The column is only filled in by builds with the `pinyin` feature, which also fill in the
+//! existing tags when they start.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaggedSticker::Table)
+                    .add_column(ColumnDef::new(TaggedSticker::Romanized).text().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaggedSticker::Table)
+                    .drop_column(TaggedSticker::Romanized)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TaggedSticker {
+    Table,
+    Romanized,
+}
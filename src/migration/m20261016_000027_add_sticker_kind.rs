@@ -0,0 +1,47 @@
+//! Records whether stickers are regular stickers, masks or custom emoji
+//!
+//! The existing stickers were all sent as stickers, so they're taken for regular ones; masks
+//! among them are told apart once their sets are refreshed with /refreshset.
+
+use sea_orm_migration::{prelude::*, sea_orm::DatabaseBackend};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // MySQL can't give text columns a default
+        let mut column = ColumnDef::new(Sticker::Kind);
+        if manager.get_database_backend() == DatabaseBackend::MySql {
+            column.string();
+        } else {
+            column.text();
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sticker::Table)
+                    .add_column(column.not_null().default("regular"))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sticker::Table)
+                    .drop_column(Sticker::Kind)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Sticker {
+    Table,
+    Kind,
+}
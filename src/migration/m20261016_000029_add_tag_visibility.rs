@@ -0,0 +1,74 @@
+//! Lets taggers keep tags to themselves
+//!
+//! Private tags are only searchable by the tagger who added them. Taggers add them after
+//! `/private on`, which is recorded along with the other settings of the tagger.
+
+use sea_orm_migration::{prelude::*, sea_orm::DatabaseBackend};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // MySQL can't give text columns a default
+        let mut column = ColumnDef::new(TaggedSticker::Visibility);
+        if manager.get_database_backend() == DatabaseBackend::MySql {
+            column.string();
+        } else {
+            column.text();
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaggedSticker::Table)
+                    .add_column(column.not_null().default("public"))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AllowedUser::Table)
+                    .add_column(
+                        ColumnDef::new(AllowedUser::PrivateTags)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AllowedUser::Table)
+                    .drop_column(AllowedUser::PrivateTags)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaggedSticker::Table)
+                    .drop_column(TaggedSticker::Visibility)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TaggedSticker {
+    Table,
+    Visibility,
+}
+
+#[derive(DeriveIden)]
+enum AllowedUser {
+    Table,
+    PrivateTags,
+}
@@ -0,0 +1,61 @@
+//! Lets users pick the order of their search results and the number of them sent at once
+//!
+//! Both are left unset unless picked with /settings, in which case the defaults of the bot apply.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite can only add a single column at a time
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserPreference::Table)
+                    .add_column(ColumnDef::new(UserPreference::SortOrder).string().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserPreference::Table)
+                    .add_column(
+                        ColumnDef::new(UserPreference::ResultsPerPage)
+                            .integer()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserPreference::Table)
+                    .drop_column(UserPreference::ResultsPerPage)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserPreference::Table)
+                    .drop_column(UserPreference::SortOrder)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserPreference {
+    Table,
+    SortOrder,
+    ResultsPerPage,
+}
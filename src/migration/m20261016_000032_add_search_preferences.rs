@@ -0,0 +1,70 @@
+//! Lets users pick the language of their searches, safe search, and whether their results are
+//! personalized
+//!
+//! All of them are left unset unless picked with /settings, in which case the defaults apply.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite can only add a single column at a time
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserPreference::Table)
+                    .add_column(ColumnDef::new(UserPreference::Language).string().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserPreference::Table)
+                    .add_column(ColumnDef::new(UserPreference::SafeSearch).boolean().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(UserPreference::Table)
+                    .add_column(
+                        ColumnDef::new(UserPreference::Personalized)
+                            .boolean()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            UserPreference::Personalized,
+            UserPreference::SafeSearch,
+            UserPreference::Language,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(UserPreference::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserPreference {
+    Table,
+    Language,
+    SafeSearch,
+    Personalized,
+}
@@ -0,0 +1,42 @@
+//! Keeps the reputation of each tagger, recomputed periodically from how their tags fare
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AllowedUser::Table)
+                    .add_column(
+                        ColumnDef::new(AllowedUser::Reputation)
+                            .double()
+                            .not_null()
+                            .default(0.0),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AllowedUser::Table)
+                    .drop_column(AllowedUser::Reputation)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AllowedUser {
+    Table,
+    Reputation,
+}
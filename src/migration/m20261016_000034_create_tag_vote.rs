@@ -0,0 +1,68 @@
+//! Stores the votes on tags, so that taggers adding a tag that a sticker has already and anyone
+//! using /vote count for or against it, instead of duplicating the tag
+//!
+//! Tags stay unique per sticker, with their tagger counting as the first vote for them.
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TagVote::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TagVote::TaggedStickerId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(TagVote::UserId).big_integer().not_null())
+                    .col(ColumnDef::new(TagVote::Value).integer().not_null())
+                    .col(
+                        ColumnDef::new(TagVote::Ts)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(TagVote::TaggedStickerId)
+                            .col(TagVote::UserId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-tag_vote-tagged_sticker_id")
+                            .from(TagVote::Table, TagVote::TaggedStickerId)
+                            .to(TaggedSticker::Table, TaggedSticker::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TagVote::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TagVote {
+    Table,
+    TaggedStickerId,
+    UserId,
+    Value,
+    Ts,
+}
+
+#[derive(DeriveIden)]
+enum TaggedSticker {
+    Table,
+    Id,
+}
@@ -0,0 +1,54 @@
+//! Records the backups of the index sent to Telegram, so that the ones beyond the retained number
+//! can be deleted from the chat again
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BackupLog::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BackupLog::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(BackupLog::ChatId).big_integer().not_null())
+                    .col(ColumnDef::new(BackupLog::MessageId).integer().not_null())
+                    .col(ColumnDef::new(BackupLog::FileName).text().not_null())
+                    .col(ColumnDef::new(BackupLog::Size).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(BackupLog::Ts)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BackupLog::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum BackupLog {
+    Table,
+    Id,
+    ChatId,
+    MessageId,
+    FileName,
+    Size,
+    Ts,
+}
@@ -0,0 +1,42 @@
+//! Records when a scheduled job last ran, which the recomputation of popularity uses to tell the
+//! choices it has counted from the ones still to be flushed
+
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ScheduledJob::Table)
+                    .add_column(
+                        ColumnDef::new(ScheduledJob::LastRun)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(ScheduledJob::Table)
+                    .drop_column(ScheduledJob::LastRun)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ScheduledJob {
+    Table,
+    LastRun,
+}
@@ -0,0 +1,99 @@
+//! Splits the perceptual hashes into indexed bands, so that the hashes close to another can be
+//! looked up without comparing against all of them
+//!
+//! Each band holds 13 bits of the hash, from its lowest bits up, and the last one the remaining 12.
+//! Hashes differing in fewer bits than there are bands have at least one band in common.
+
+use sea_orm_migration::prelude::*;
+
+const BAND_BITS: u32 = 13;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite can only add a single column at a time
+        for band in BANDS {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(StickerHash::Table)
+                        .add_column(ColumnDef::new(band).integer().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // the bits are shifted the same by every database, as the masks drop the sign bits
+        let mut update = Query::update();
+        update.table(StickerHash::Table);
+        for (i, band) in BANDS.into_iter().enumerate() {
+            let shift = i as u32 * BAND_BITS;
+            let mask = (1i64 << BAND_BITS.min(64 - shift)) - 1;
+            update.value(
+                band,
+                Expr::col(StickerHash::Hash)
+                    .right_shift(shift as i32)
+                    .binary(BinOper::BitAnd, mask),
+            );
+        }
+        update.and_where(Expr::col(StickerHash::Hash).is_not_null());
+        manager.exec_stmt(update).await?;
+
+        for band in BANDS {
+            manager
+                .create_index(
+                    Index::create()
+                        .name(format!("idx-sticker_hash-{}", band.to_string()))
+                        .table(StickerHash::Table)
+                        .col(band)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for band in BANDS {
+            manager
+                .drop_index(
+                    Index::drop()
+                        .name(format!("idx-sticker_hash-{}", band.to_string()))
+                        .table(StickerHash::Table)
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(StickerHash::Table)
+                        .drop_column(band)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+const BANDS: [StickerHash; 5] = [
+    StickerHash::Band0,
+    StickerHash::Band1,
+    StickerHash::Band2,
+    StickerHash::Band3,
+    StickerHash::Band4,
+];
+
+#[derive(DeriveIden, Clone, Copy)]
+enum StickerHash {
+    Table,
+    Hash,
+    Band0,
+    Band1,
+    Band2,
+    Band3,
+    Band4,
+}
@@ -0,0 +1,181 @@
+//! Makes tags unique per sticker within the scope they're searchable in, rather than per sticker
+//!
+//! A private tag, or one scoped to a group chat, used to keep everyone else from adding the same
+//! tag for everyone. The scope names the chat and the tagger of private tags, empty for the tags
+//! searchable by everyone, and takes the place of the sticker and the tag alone in the unique
+//! index.
+
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ConnectionTrait, DatabaseBackend},
+};
+
+use crate::model::tagged_sticker;
+
+const OLD_INDEX_NAME: &str = "idx-tagged_sticker-sticker_id-tag";
+const INDEX_NAME: &str = "idx-tagged_sticker-sticker_id-tag-scope";
+// longest prefix of the tags indexed on MySQL, which keeps the key under its limit of 3072 bytes
+const TAG_PREFIX_LEN: u32 = 255;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaggedSticker::Table)
+                    .add_column(
+                        ColumnDef::new(TaggedSticker::Scope)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        let backend = db.get_database_backend();
+        let select = Query::select()
+            .distinct()
+            .columns([
+                TaggedSticker::ChatId,
+                TaggedSticker::Visibility,
+                TaggedSticker::TaggerId,
+            ])
+            .from(TaggedSticker::Table)
+            .cond_where(
+                Cond::any()
+                    .add(Expr::col(TaggedSticker::ChatId).is_not_null())
+                    .add(Expr::col(TaggedSticker::Visibility).ne(tagged_sticker::PUBLIC)),
+            )
+            .to_owned();
+        for row in db.query_all(backend.build(&select)).await? {
+            let chat_id: Option<i64> = row.try_get("", "chat_id")?;
+            let visibility: String = row.try_get("", "visibility")?;
+            let tagger_id: i32 = row.try_get("", "tagger_id")?;
+            let chat = match chat_id {
+                Some(chat_id) => Expr::col(TaggedSticker::ChatId).eq(chat_id),
+                None => Expr::col(TaggedSticker::ChatId).is_null(),
+            };
+            let update = Query::update()
+                .table(TaggedSticker::Table)
+                .value(
+                    TaggedSticker::Scope,
+                    tagged_sticker::scope(chat_id, &visibility, tagger_id),
+                )
+                .and_where(chat)
+                .and_where(Expr::col(TaggedSticker::Visibility).eq(visibility))
+                .and_where(Expr::col(TaggedSticker::TaggerId).eq(tagger_id))
+                .to_owned();
+            db.execute(backend.build(&update)).await?;
+        }
+
+        // the new index is created first, as MySQL needs an index starting with the sticker for
+        // its foreign key at all times
+        let mut index = Index::create();
+        index
+            .name(INDEX_NAME)
+            .table(TaggedSticker::Table)
+            .col(TaggedSticker::StickerId)
+            .unique();
+        if manager.get_database_backend() == DatabaseBackend::MySql {
+            index.col((TaggedSticker::Tag, TAG_PREFIX_LEN));
+        } else {
+            index.col(TaggedSticker::Tag);
+        }
+        index.col(TaggedSticker::Scope);
+        manager.create_index(index.to_owned()).await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(OLD_INDEX_NAME)
+                    .table(TaggedSticker::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // the same tag may now be on a sticker in several scopes, of which the earliest is kept;
+        // MySQL can't select from the table it deletes from, unless the selection is materialized
+        // in a derived table first
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(TaggedSticker::Table)
+                    .and_where(
+                        Expr::col(TaggedSticker::Id).not_in_subquery(
+                            Query::select()
+                                .column(TaggedSticker::Id)
+                                .from_subquery(
+                                    Query::select()
+                                        .expr_as(
+                                            Expr::col(TaggedSticker::Id).min(),
+                                            TaggedSticker::Id,
+                                        )
+                                        .from(TaggedSticker::Table)
+                                        .group_by_columns([
+                                            TaggedSticker::StickerId,
+                                            TaggedSticker::Tag,
+                                        ])
+                                        .to_owned(),
+                                    Kept::Table,
+                                )
+                                .to_owned(),
+                        ),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut index = Index::create();
+        index
+            .name(OLD_INDEX_NAME)
+            .table(TaggedSticker::Table)
+            .col(TaggedSticker::StickerId)
+            .unique();
+        if manager.get_database_backend() == DatabaseBackend::MySql {
+            index.col((TaggedSticker::Tag, TAG_PREFIX_LEN));
+        } else {
+            index.col(TaggedSticker::Tag);
+        }
+        manager.create_index(index.to_owned()).await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name(INDEX_NAME)
+                    .table(TaggedSticker::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(TaggedSticker::Table)
+                    .drop_column(TaggedSticker::Scope)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum TaggedSticker {
+    Table,
+    Id,
+    StickerId,
+    TaggerId,
+    Tag,
+    ChatId,
+    Visibility,
+    Scope,
+}
+
+#[derive(DeriveIden)]
+enum Kept {
+    Table,
+}
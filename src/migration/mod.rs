@@ -0,0 +1,93 @@
+//! Database schema migrations
+//!
+//! Every change to the schema should be added as a new migration, instead of modifying the
+//! existing ones, so that existing deployments can be upgraded without losing data.
+
+use sea_orm_migration::prelude::*;
+
+mod m20261016_000001_create_tables;
+mod m20261016_000002_add_sticker_emoji;
+mod m20261016_000003_add_sticker_last_used;
+mod m20261016_000004_create_sticker_usage;
+mod m20261016_000005_unique_sticker_tag;
+mod m20261016_000006_create_audit_log;
+mod m20261016_000007_tagged_sticker_foreign_keys;
+mod m20261016_000008_add_indexes;
+mod m20261016_000009_add_sticker_dead;
+mod m20261016_000010_create_report;
+mod m20261016_000011_add_tagged_sticker_approved;
+mod m20261016_000012_add_sticker_indexed_at;
+mod m20261016_000013_add_sticker_metadata;
+mod m20261016_000014_create_user_preference;
+mod m20261016_000015_create_chosen_result;
+mod m20261016_000016_create_missed_query;
+mod m20261016_000017_create_scheduled_job;
+mod m20261016_000018_create_processed_update;
+mod m20261016_000019_add_tag_propagation;
+mod m20261016_000020_create_sticker_text;
+mod m20261016_000021_create_sticker_hash;
+mod m20261016_000022_create_api_token;
+mod m20261016_000023_create_favorite;
+mod m20261016_000024_add_tag_namespace;
+mod m20261016_000025_add_tag_language;
+mod m20261016_000026_add_tag_romanized;
+mod m20261016_000027_add_sticker_kind;
+mod m20261016_000028_add_tag_chat_id;
+mod m20261016_000029_add_tag_visibility;
+mod m20261016_000030_add_tag_deleted_at;
+mod m20261016_000031_add_result_preferences;
+mod m20261016_000032_add_search_preferences;
+mod m20261016_000033_add_tagger_reputation;
+mod m20261016_000034_create_tag_vote;
+mod m20261016_000035_create_backup_log;
+mod m20261016_000036_add_scheduled_job_last_run;
+mod m20261016_000037_add_sticker_hash_bands;
+mod m20261016_000038_add_tag_scope;
+
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20261016_000001_create_tables::Migration),
+            Box::new(m20261016_000002_add_sticker_emoji::Migration),
+            Box::new(m20261016_000003_add_sticker_last_used::Migration),
+            Box::new(m20261016_000004_create_sticker_usage::Migration),
+            Box::new(m20261016_000005_unique_sticker_tag::Migration),
+            Box::new(m20261016_000006_create_audit_log::Migration),
+            Box::new(m20261016_000007_tagged_sticker_foreign_keys::Migration),
+            Box::new(m20261016_000008_add_indexes::Migration),
+            Box::new(m20261016_000009_add_sticker_dead::Migration),
+            Box::new(m20261016_000010_create_report::Migration),
+            Box::new(m20261016_000011_add_tagged_sticker_approved::Migration),
+            Box::new(m20261016_000012_add_sticker_indexed_at::Migration),
+            Box::new(m20261016_000013_add_sticker_metadata::Migration),
+            Box::new(m20261016_000014_create_user_preference::Migration),
+            Box::new(m20261016_000015_create_chosen_result::Migration),
+            Box::new(m20261016_000016_create_missed_query::Migration),
+            Box::new(m20261016_000017_create_scheduled_job::Migration),
+            Box::new(m20261016_000018_create_processed_update::Migration),
+            Box::new(m20261016_000019_add_tag_propagation::Migration),
+            Box::new(m20261016_000020_create_sticker_text::Migration),
+            Box::new(m20261016_000021_create_sticker_hash::Migration),
+            Box::new(m20261016_000022_create_api_token::Migration),
+            Box::new(m20261016_000023_create_favorite::Migration),
+            Box::new(m20261016_000024_add_tag_namespace::Migration),
+            Box::new(m20261016_000025_add_tag_language::Migration),
+            Box::new(m20261016_000026_add_tag_romanized::Migration),
+            Box::new(m20261016_000027_add_sticker_kind::Migration),
+            Box::new(m20261016_000028_add_tag_chat_id::Migration),
+            Box::new(m20261016_000029_add_tag_visibility::Migration),
+            Box::new(m20261016_000030_add_tag_deleted_at::Migration),
+            Box::new(m20261016_000031_add_result_preferences::Migration),
+            Box::new(m20261016_000032_add_search_preferences::Migration),
+            Box::new(m20261016_000033_add_tagger_reputation::Migration),
+            Box::new(m20261016_000034_create_tag_vote::Migration),
+            Box::new(m20261016_000035_create_backup_log::Migration),
+            Box::new(m20261016_000036_add_scheduled_job_last_run::Migration),
+            Box::new(m20261016_000037_add_sticker_hash_bands::Migration),
+            Box::new(m20261016_000038_add_tag_scope::Migration),
+        ]
+    }
+}
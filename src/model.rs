@@ -3,24 +3,77 @@
 pub mod sticker {
     use sea_orm::entity::prelude::*;
 
+    pub const REGULAR: &str = "regular";
+    pub const MASK: &str = "mask";
+    pub const CUSTOM_EMOJI: &str = "custom_emoji";
+    pub const ANIMATION: &str = "animation";
+    pub const PHOTO: &str = "photo";
+    pub const VIDEO: &str = "video";
+
     #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
     #[sea_orm(table_name = "sticker")]
     pub struct Model {
         #[sea_orm(primary_key)]
         pub id: i32,
 
+        /// Identifies the sticker across bots and over time, but can't be used to send it
         #[sea_orm(unique)]
         pub file_unique_id: String,
 
+        /// Used to send the sticker; Telegram may change it, so it's updated whenever the
+        /// sticker is seen again
         pub file_id: String,
 
+        /// Empty for animations, photos and videos, which don't come in sets
         pub set_name: String,
 
         pub popularity: i64,
+
+        /// The emoji associated with the sticker, with variation selectors stripped
+        pub emoji: Option<String>,
+
+        /// When the sticker was last chosen from inline query results
+        pub last_used: Option<DateTimeUtc>,
+
+        /// Whether the sticker (or its whole set) was deleted from Telegram, so that it can no
+        /// longer be sent; dead stickers are hidden from inline query results
+        pub dead: bool,
+
+        /// When the sticker was indexed; stickers indexed before this was recorded are dated by
+        /// their oldest tag, if they had any
+        pub indexed_at: Option<DateTimeUtc>,
+
+        /// Format of the sticker; stickers that are neither animated nor videos are static
+        /// images. Recorded along with the rest of the metadata since it was introduced, and
+        /// updated whenever the sticker is seen again.
+        pub is_animated: bool,
+        pub is_video: bool,
+
+        /// Dimensions of the sticker in pixels; unknown for stickers not seen since indexed
+        pub width: Option<i32>,
+        pub height: Option<i32>,
+
+        /// File id of the thumbnail of the sticker, if it has one
+        #[sea_orm(column_type = "Text", nullable)]
+        pub thumb_file_id: Option<String>,
+
+        /// One of the kind constants in this module; custom emoji are indexed from the emoji
+        /// packs they come from, and animations (GIFs), photos and videos on their own. All of
+        /// them are found by searches like any other sticker, and sent as what they are.
+        pub kind: String,
     }
 
     #[derive(Debug, DeriveRelation, EnumIter)]
-    pub enum Relation {}
+    pub enum Relation {
+        #[sea_orm(has_many = "super::tagged_sticker::Entity")]
+        TaggedSticker,
+    }
+
+    impl Related<super::tagged_sticker::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::TaggedSticker.def()
+        }
+    }
 
     impl ActiveModelBehavior for ActiveModel {}
 }
@@ -28,6 +81,9 @@ pub mod sticker {
 pub mod tagged_sticker {
     use sea_orm::entity::prelude::*;
 
+    pub const PUBLIC: &str = "public";
+    pub const PRIVATE: &str = "private";
+
     #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
     #[sea_orm(table_name = "tagged_sticker")]
     pub struct Model {
@@ -40,10 +96,87 @@ pub mod tagged_sticker {
         pub tagger_id: i32,
 
         pub ts: DateTimeUtc,
+
+        /// Whether the tag is used for searching; the tags of taggers who aren't trusted yet
+        /// await the approval of a moderator
+        pub approved: bool,
+
+        /// The tag this tag was propagated from to the other stickers in the set, if any
+        pub inherited_from: Option<i32>,
+
+        /// Namespace of tags of the form `namespace:value`, e.g. `char` for `char:miku`; the tag
+        /// itself keeps the whole text
+        pub namespace: Option<String>,
+
+        /// Two-letter language code of the tag, given by the tagger or detected from its script,
+        /// if known
+        pub language: Option<String>,
+
+        /// Pinyin of Chinese tags without tones, e.g. `kuku` for `哭哭`, matched by searches along
+        /// with the tag; only filled in by builds with the `pinyin` feature
+        #[sea_orm(column_type = "Text", nullable)]
+        pub romanized: Option<String>,
+
+        /// Group chat the tag was added in with `group_scoped_tags` on, whose members are the
+        /// only ones finding the sticker by it; tags without one are searchable by everyone
+        pub chat_id: Option<i64>,
+
+        /// [`PUBLIC`] or [`PRIVATE`]; private tags are only searchable by the tagger who added
+        /// them
+        #[sea_orm(column_type = "Text")]
+        pub visibility: String,
+
+        /// When the tag was removed; removed tags are kept in the trash, where they're left out of
+        /// searches and can be restored until they're purged
+        pub deleted_at: Option<DateTimeUtc>,
+
+        /// Telegram user id of whoever removed the tag, who may restore it; unknown for the tags
+        /// removed on the dashboard
+        pub deleted_by: Option<i64>,
+
+        /// Whom the tag is visible to as given by [`scope`], within which a tag is added to a
+        /// sticker only once
+        pub scope: String,
+    }
+
+    /// Key of the scope a tag is searchable in, naming its group chat and the tagger of private
+    /// tags, empty for the tags searchable by everyone; the same tag may be on a sticker once in
+    /// each scope, so that scoped tags don't keep anyone else from adding it
+    pub fn scope(chat_id: Option<i64>, visibility: &str, tagger_id: i32) -> String {
+        let chat = chat_id.map(|chat_id| format!("chat:{chat_id}"));
+        let tagger = (visibility == PRIVATE).then(|| format!("tagger:{tagger_id}"));
+        chat.into_iter().chain(tagger).collect::<Vec<_>>().join(" ")
     }
 
     #[derive(Debug, DeriveRelation, EnumIter)]
-    pub enum Relation {}
+    pub enum Relation {
+        #[sea_orm(
+            belongs_to = "super::sticker::Entity",
+            from = "Column::StickerId",
+            to = "super::sticker::Column::Id",
+            on_delete = "Cascade"
+        )]
+        Sticker,
+        #[sea_orm(
+            belongs_to = "super::user::Entity",
+            from = "Column::TaggerId",
+            to = "super::user::Column::Id",
+            on_delete = "Cascade"
+        )]
+        Tagger,
+    }
+
+    impl Related<super::sticker::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::Sticker.def()
+        }
+    }
+
+    impl Related<super::user::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::Tagger.def()
+        }
+    }
 
     impl ActiveModelBehavior for ActiveModel {}
 }
@@ -63,6 +196,405 @@ pub mod user {
         #[sea_orm(column_type = "Text")]
         pub username: String,
         pub allowed: bool,
+
+        /// Whether the tags added by the user are propagated to every sticker in the set
+        pub propagate_tags: bool,
+
+        /// Whether the tags added by the user are private to them
+        pub private_tags: bool,
+
+        /// How well the tags of the user fare, see [`crate::reputation`]; recomputed periodically
+        pub reputation: f64,
+    }
+
+    #[derive(Debug, DeriveRelation, EnumIter)]
+    pub enum Relation {
+        #[sea_orm(has_many = "super::tagged_sticker::Entity")]
+        TaggedSticker,
+    }
+
+    impl Related<super::tagged_sticker::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::TaggedSticker.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod sticker_usage {
+    use sea_orm::entity::prelude::*;
+
+    /// Number of times a Telegram user has chosen a sticker from inline query results
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "sticker_usage")]
+    pub struct Model {
+        /// Telegram user id of the user, who is not necessarily a registered tagger
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub user_id: i64,
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub sticker_id: i32,
+
+        pub times_chosen: i64,
+        pub last_used: DateTimeUtc,
+    }
+
+    #[derive(Debug, DeriveRelation, EnumIter)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod favorite {
+    use sea_orm::entity::prelude::*;
+
+    /// Sticker added by a Telegram user to their favorites
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "favorite")]
+    pub struct Model {
+        /// Telegram user id of the user, who is not necessarily a registered tagger
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub user_id: i64,
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub sticker_id: i32,
+
+        pub ts: DateTimeUtc,
+    }
+
+    #[derive(Debug, DeriveRelation, EnumIter)]
+    pub enum Relation {
+        #[sea_orm(
+            belongs_to = "super::sticker::Entity",
+            from = "Column::StickerId",
+            to = "super::sticker::Column::Id",
+            on_delete = "Cascade"
+        )]
+        Sticker,
+    }
+
+    impl Related<super::sticker::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::Sticker.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod tag_vote {
+    use sea_orm::entity::prelude::*;
+
+    pub const UP: i32 = 1;
+    pub const DOWN: i32 = -1;
+
+    /// Vote of a Telegram user for or against a tag, cast by adding a tag that the sticker has
+    /// already or with /vote; the tagger of the tag counts as a vote for it on top of these
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "tag_vote")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub tagged_sticker_id: i32,
+        /// Telegram user id of the voter, who is not necessarily a registered tagger
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub user_id: i64,
+
+        /// [`UP`] or [`DOWN`]
+        pub value: i32,
+        pub ts: DateTimeUtc,
+    }
+
+    #[derive(Debug, DeriveRelation, EnumIter)]
+    pub enum Relation {
+        #[sea_orm(
+            belongs_to = "super::tagged_sticker::Entity",
+            from = "Column::TaggedStickerId",
+            to = "super::tagged_sticker::Column::Id",
+            on_delete = "Cascade"
+        )]
+        TaggedSticker,
+    }
+
+    impl Related<super::tagged_sticker::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::TaggedSticker.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod chosen_result {
+    use sea_orm::entity::prelude::*;
+
+    /// A sticker chosen from inline query results, logged for analytics
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "chosen_result")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+
+        pub sticker_id: i32,
+
+        /// Telegram user id of the user, who is not necessarily a registered tagger
+        pub user_id: i64,
+
+        /// The inline query the sticker was found with, as typed by the user
+        #[sea_orm(column_type = "Text")]
+        pub query: String,
+
+        pub ts: DateTimeUtc,
+    }
+
+    #[derive(Debug, DeriveRelation, EnumIter)]
+    pub enum Relation {
+        #[sea_orm(
+            belongs_to = "super::sticker::Entity",
+            from = "Column::StickerId",
+            to = "super::sticker::Column::Id",
+            on_delete = "Cascade"
+        )]
+        Sticker,
+    }
+
+    impl Related<super::sticker::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::Sticker.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod sticker_text {
+    use sea_orm::entity::prelude::*;
+
+    /// Text recognized in a sticker, which is searched along with its tags
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "sticker_text")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub sticker_id: i32,
+
+        /// The recognized text with its whitespace collapsed, which is empty for stickers
+        /// without any text, so that they are not recognized again
+        #[sea_orm(column_type = "Text")]
+        pub text: String,
+
+        pub ts: DateTimeUtc,
+    }
+
+    #[derive(Debug, DeriveRelation, EnumIter)]
+    pub enum Relation {
+        #[sea_orm(
+            belongs_to = "super::sticker::Entity",
+            from = "Column::StickerId",
+            to = "super::sticker::Column::Id",
+            on_delete = "Cascade"
+        )]
+        Sticker,
+    }
+
+    impl Related<super::sticker::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::Sticker.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod sticker_hash {
+    use sea_orm::{entity::prelude::*, Set};
+
+    /// Perceptual hash of a sticker, to find the stickers looking alike in other sets
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "sticker_hash")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub sticker_id: i32,
+
+        /// The bits of the hash, or nothing if the sticker has no image to hash, so that it's
+        /// not tried again
+        pub hash: Option<i64>,
+
+        /// The bands of the hash as split by [`crate::phash::bands`], to look up the hashes
+        /// close to it by
+        pub band0: Option<i32>,
+        pub band1: Option<i32>,
+        pub band2: Option<i32>,
+        pub band3: Option<i32>,
+        pub band4: Option<i32>,
+
+        pub ts: DateTimeUtc,
+    }
+
+    /// Columns of the bands of the hashes, in the order of [`crate::phash::bands`]
+    pub const BAND_COLUMNS: [Column; crate::phash::BANDS] = [
+        Column::Band0,
+        Column::Band1,
+        Column::Band2,
+        Column::Band3,
+        Column::Band4,
+    ];
+
+    /// The hash of a sticker to be stored along with its bands, where the bits are stored as they
+    /// are since databases lack unsigned integers
+    pub fn new_active_model(sticker_id: i32, hash: Option<u64>, ts: DateTimeUtc) -> ActiveModel {
+        let bands = hash.map(crate::phash::bands);
+        let band = |i: usize| Set(bands.map(|bands| bands[i]));
+        ActiveModel {
+            sticker_id: Set(sticker_id),
+            hash: Set(hash.map(|hash| hash as i64)),
+            band0: band(0),
+            band1: band(1),
+            band2: band(2),
+            band3: band(3),
+            band4: band(4),
+            ts: Set(ts),
+        }
+    }
+
+    #[derive(Debug, DeriveRelation, EnumIter)]
+    pub enum Relation {
+        #[sea_orm(
+            belongs_to = "super::sticker::Entity",
+            from = "Column::StickerId",
+            to = "super::sticker::Column::Id",
+            on_delete = "Cascade"
+        )]
+        Sticker,
+    }
+
+    impl Related<super::sticker::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::Sticker.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod api_token {
+    use sea_orm::entity::prelude::*;
+
+    /// Token of a tagger for the HTTP API, of which only the hash is stored
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "api_token")]
+    pub struct Model {
+        /// Hex-encoded SHA-256 hash of the token
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub token_hash: String,
+
+        pub user_id: i32,
+        pub created_at: DateTimeUtc,
+    }
+
+    #[derive(Debug, DeriveRelation, EnumIter)]
+    pub enum Relation {
+        #[sea_orm(
+            belongs_to = "super::user::Entity",
+            from = "Column::UserId",
+            to = "super::user::Column::Id",
+            on_delete = "Cascade"
+        )]
+        User,
+    }
+
+    impl Related<super::user::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::User.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod missed_query {
+    use sea_orm::entity::prelude::*;
+
+    /// Search terms of inline queries that returned no results, to show taggers what's missing
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "missed_query")]
+    pub struct Model {
+        /// The search terms, joined like the tags in the audit log
+        #[sea_orm(primary_key, auto_increment = false, column_type = "Text")]
+        pub query: String,
+
+        pub times_missed: i64,
+        pub last_missed: DateTimeUtc,
+    }
+
+    #[derive(Debug, DeriveRelation, EnumIter)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod scheduled_job {
+    use sea_orm::entity::prelude::*;
+
+    /// A background job run by only one of the instances of the bot sharing the database
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "scheduled_job")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub name: String,
+
+        /// The job may be claimed by any instance once this time has passed
+        pub next_run: DateTimeUtc,
+
+        /// Time the job last ran, for the jobs that need to know; the choices made until the
+        /// last recomputation of popularity are counted by it, and no longer by the flushes
+        pub last_run: Option<DateTimeUtc>,
+    }
+
+    #[derive(Debug, DeriveRelation, EnumIter)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod backup_log {
+    use sea_orm::entity::prelude::*;
+
+    /// A backup of the index sent to Telegram, whose message is deleted again once newer backups
+    /// replace it
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "backup_log")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+
+        /// Chat and message the backup was sent as
+        pub chat_id: i64,
+        pub message_id: i32,
+
+        #[sea_orm(column_type = "Text")]
+        pub file_name: String,
+        /// Size of the backup in bytes
+        pub size: i64,
+
+        pub ts: DateTimeUtc,
+    }
+
+    #[derive(Debug, DeriveRelation, EnumIter)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod processed_update {
+    use sea_orm::entity::prelude::*;
+
+    /// An update from Telegram that has been handled by one of the instances of the bot
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "processed_update")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub update_id: i64,
+
+        /// When the update was handled, after which it's forgotten in a while
+        pub ts: DateTimeUtc,
     }
 
     #[derive(Debug, DeriveRelation, EnumIter)]
@@ -70,3 +602,138 @@ pub mod user {
 
     impl ActiveModelBehavior for ActiveModel {}
 }
+
+pub mod user_preference {
+    use sea_orm::entity::prelude::*;
+
+    /// Preferences set by a Telegram user, who is not necessarily a registered tagger
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "user_preference")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub user_id: i64,
+
+        /// Type of stickers shown for queries without a `type:` filter, as named in the filter
+        pub sticker_type: Option<String>,
+
+        /// Order of the search results, as named in [`crate::ranking::SortOrder::as_str`]
+        pub sort_order: Option<String>,
+
+        /// Number of inline query results sent at once
+        pub results_per_page: Option<i32>,
+
+        /// Two-letter code of the language whose tags are preferred in the search results,
+        /// instead of the language of the Telegram client
+        pub language: Option<String>,
+
+        /// Whether the stickers tagged `nsfw` are hidden
+        pub safe_search: Option<bool>,
+
+        /// Whether the stickers chosen by the user before are ranked first, where the bot does
+        pub personalized: Option<bool>,
+    }
+
+    #[derive(Debug, DeriveRelation, EnumIter)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod audit_log {
+    use sea_orm::entity::prelude::*;
+
+    pub const UNTAG: &str = "untag";
+    pub const ALLOW: &str = "allow";
+    pub const DENY: &str = "deny";
+    pub const REVOKE: &str = "revoke";
+    pub const PURGE: &str = "purge";
+    pub const REPORT_UNTAG: &str = "report_untag";
+    pub const REPORT_DISMISS: &str = "report_dismiss";
+    pub const REVIEW_APPROVE: &str = "review_approve";
+    pub const REVIEW_REJECT: &str = "review_reject";
+    pub const CLEAR_TAGS: &str = "clear_tags";
+    pub const CHOWN: &str = "chown";
+    pub const RETAG: &str = "retag";
+    pub const MERGE: &str = "merge";
+    pub const VOTE_REMOVE: &str = "vote_remove";
+
+    /// A destructive or permission-changing action, recorded for auditing
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "audit_log")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+
+        pub ts: DateTimeUtc,
+
+        /// One of the action constants in this module
+        pub action: String,
+
+        /// Telegram user id and username of whoever performed the action
+        pub actor_user_id: Option<i64>,
+        #[sea_orm(column_type = "Text", nullable)]
+        pub actor_username: Option<String>,
+
+        /// Sticker affected by the action
+        pub sticker_id: Option<i32>,
+
+        /// Username of the user affected by the action
+        #[sea_orm(column_type = "Text", nullable)]
+        pub target_username: Option<String>,
+
+        /// Space-separated tags affected by the action, with phrase tags quoted
+        #[sea_orm(column_type = "Text", nullable)]
+        pub tags: Option<String>,
+    }
+
+    #[derive(Debug, DeriveRelation, EnumIter)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+pub mod report {
+    use sea_orm::entity::prelude::*;
+
+    /// Report of bad tags on a sticker, awaiting a moderator
+    #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+    #[sea_orm(table_name = "report")]
+    pub struct Model {
+        #[sea_orm(primary_key)]
+        pub id: i32,
+
+        pub sticker_id: i32,
+
+        /// Telegram user id and username of the reporter, who is not necessarily a tagger
+        pub reporter_user_id: i64,
+        #[sea_orm(column_type = "Text", nullable)]
+        pub reporter_username: Option<String>,
+
+        #[sea_orm(column_type = "Text", nullable)]
+        pub reason: Option<String>,
+
+        pub ts: DateTimeUtc,
+
+        /// Whether a moderator has dismissed the report
+        pub resolved: bool,
+    }
+
+    #[derive(Debug, DeriveRelation, EnumIter)]
+    pub enum Relation {
+        #[sea_orm(
+            belongs_to = "super::sticker::Entity",
+            from = "Column::StickerId",
+            to = "super::sticker::Column::Id",
+            on_delete = "Cascade"
+        )]
+        Sticker,
+    }
+
+    impl Related<super::sticker::Entity> for Entity {
+        fn to() -> RelationDef {
+            Relation::Sticker.def()
+        }
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
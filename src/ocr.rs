@@ -0,0 +1,41 @@
+//! Recognition of the text inside stickers, so that they can be found by it
+//!
+//! Only built with the `ocr` feature, since tesseract and leptonica must be installed for it.
+
+use std::io::Cursor;
+
+use image::ImageOutputFormat;
+use itertools::Itertools;
+
+use crate::phash;
+
+#[derive(Debug, thiserror::Error)]
+pub enum OcrError {
+    #[error("Failed to decode the image: {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("Tesseract failed: {0}")]
+    Tesseract(#[from] tesseract::TesseractError),
+}
+
+/// Recognizes the text in a sticker image (or thumbnail) in any of the given tesseract
+/// languages, e.g. `eng+chi_tra`. Blocks for a while, so it's best run with `spawn_blocking`.
+pub fn recognize(image: &[u8], languages: &str) -> Result<String, OcrError> {
+    let flattened = phash::decode_on_white(image)?;
+
+    let mut png = Cursor::new(Vec::new());
+    flattened.write_to(&mut png, ImageOutputFormat::Png)?;
+    let text = tesseract::Tesseract::new(None, Some(languages))
+        .map_err(tesseract::TesseractError::from)?
+        .set_image_from_mem(png.get_ref())
+        .map_err(tesseract::TesseractError::from)?
+        .get_text()
+        .map_err(tesseract::TesseractError::from)?;
+
+    Ok(normalize_text(&text))
+}
+
+/// Collapses the whitespace of recognized text, which is laid out in lines
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace().join(" ")
+}
@@ -0,0 +1,65 @@
+//! Perceptual hashes of sticker images, which differ in only a few bits for images looking alike
+//!
+//! The hashes are difference hashes: the image is shrunk to 9x8 gray pixels, and each bit tells
+//! whether a pixel is darker than its right neighbor. They survive rescaling and recompression,
+//! which is what tells apart copies of a sticker in other sets.
+
+use image::{imageops::FilterType, GrayImage, ImageError, Luma, Rgba, RgbaImage};
+
+/// Width and height of the shrunk image, which is one pixel wider than the hash has bits per row
+const HASH_WIDTH: u32 = 9;
+const HASH_HEIGHT: u32 = 8;
+
+/// Decodes a sticker image (or thumbnail), putting it on a white background, since stickers are
+/// transparent and mostly shown on light backgrounds
+pub fn decode_on_white(image: &[u8]) -> Result<RgbaImage, ImageError> {
+    let decoded = image::load_from_memory(image)?.into_rgba8();
+    let flattened = RgbaImage::from_fn(decoded.width(), decoded.height(), |x, y| {
+        let Rgba([r, g, b, a]) = *decoded.get_pixel(x, y);
+        let over_white = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+        Rgba([over_white(r), over_white(g), over_white(b), 255])
+    });
+    Ok(flattened)
+}
+
+/// Perceptual hash of a sticker image (or thumbnail)
+pub fn hash(image: &[u8]) -> Result<u64, ImageError> {
+    let flattened = decode_on_white(image)?;
+    let gray = GrayImage::from_fn(flattened.width(), flattened.height(), |x, y| {
+        let Rgba([r, g, b, _]) = *flattened.get_pixel(x, y);
+        let luma = (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000;
+        Luma([luma as u8])
+    });
+    let small = image::imageops::resize(&gray, HASH_WIDTH, HASH_HEIGHT, FilterType::Triangle);
+
+    let mut hash = 0;
+    for y in 0..HASH_HEIGHT {
+        for x in 0..HASH_WIDTH - 1 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    Ok(hash)
+}
+
+/// Number of differing bits of two hashes; images with a distance of a few bits look alike
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Number of bits of each band of a hash, and the number of bands, the last of which holds the
+/// remaining bits; hashes differing in fewer bits than there are bands have a band in common, so
+/// the hashes close to another are found by looking up its bands
+const BAND_BITS: u32 = 13;
+pub const BANDS: usize = 5;
+
+/// The bands of a hash, from its lowest bits up
+pub fn bands(hash: u64) -> [i32; BANDS] {
+    std::array::from_fn(|band| {
+        let shift = band as u32 * BAND_BITS;
+        let mask = (1 << BAND_BITS.min(64 - shift)) - 1;
+        ((hash >> shift) & mask) as i32
+    })
+}
@@ -0,0 +1,690 @@
+//! Parsing of inline queries
+//!
+//! A query is a whitespace-separated list of terms. Terms prefixed with a minus sign exclude the
+//! stickers carrying a tag that contains them, e.g. `cat -dog` finds cats that aren't tagged dog.
+//! Double quotes group several words into a single term, e.g. `"good morning"` or
+//! `-"bad cat"`, which matches the phrase tags added the same way with /tag.
+//!
+//! Terms in Chinese, Japanese or Korean are matched by their character n-grams, see
+//! [`term_tokens`].
+//!
+//! Terms of the form `field:value` filter on the stickers themselves instead of their tags:
+//!
+//! - `set:<name>` keeps the stickers whose set name contains `<name>`; several of them keep the
+//!   stickers from any of the matching sets. Without other terms, every sticker from the sets
+//!   is listed, whether tagged or not
+//! - `type:<type>` keeps the stickers of the given [`StickerType`], e.g. `type:static`
+//! - `<namespace>:` keeps the stickers having a tag in the namespace, e.g. `char:` for tags like
+//!   `char:miku`; several of them keep the stickers having a tag in any of them. Without other
+//!   terms, every such sticker is listed. Two-letter names are taken as languages rather than
+//!   namespaces, see [`crate::language`]
+//!
+//! Namespaced tags are otherwise searched like any other, e.g. `char:miku` or just `miku`.
+//!
+//! A term of the form `~<sticker id>` lists the stickers sharing the most tags with the sticker
+//! instead, ignoring the other search terms; it's inserted by the button sent by /similar.
+//!
+//! The term `fav:` restricts the results to the favorites of the querying user, added with /fav.
+//! Without other terms, all of the favorites are listed, most recently added first. The term
+//! `recent:` does the same for the stickers recently chosen by the user.
+//!
+//! Stickers tagged [`NSFW_TAG`] are left out of the results of users with safe search on, which
+//! it is by default, unless the query has the term `nsfw:`.
+
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use sea_orm::{
+    sea_query::{Expr, Query, SimpleExpr},
+    ColumnTrait, Condition,
+};
+
+use crate::{language, model, ranking::SortOrder};
+
+/// Prefix of the terms filtering on the sticker set name
+pub const SET_PREFIX: &str = "set:";
+
+/// Prefix of the terms filtering on the sticker type
+const TYPE_PREFIX: &str = "type:";
+
+/// Prefix of the term asking for the stickers similar to a sticker, given by its id
+pub const SIMILAR_PREFIX: &str = "~";
+
+/// Term restricting the results to the favorites of the user
+pub const FAVORITES_TERM: &str = "fav:";
+
+/// Term restricting the results to the stickers recently chosen by the user
+pub const RECENT_TERM: &str = "recent:";
+
+/// Tag of the stickers hidden from users with safe search on, as if they excluded it
+pub const NSFW_TAG: &str = "nsfw";
+
+/// Term showing the stickers tagged [`NSFW_TAG`] despite safe search
+pub const NSFW_TERM: &str = "nsfw:";
+
+/// Format of a sticker, as far as users care
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StickerType {
+    /// Still images
+    Static,
+    /// Moving stickers, whether animated or videos
+    Animated,
+    /// Video stickers, along with GIFs and videos
+    Video,
+    /// Custom emoji, whatever their format
+    Emoji,
+    /// GIFs, which are indexed along with the stickers
+    Gif,
+    /// Photos, which are indexed along with the stickers
+    Photo,
+    /// Stickers and custom emoji, rather than other media
+    Sticker,
+}
+
+impl StickerType {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "static" => Some(Self::Static),
+            "animated" => Some(Self::Animated),
+            "video" => Some(Self::Video),
+            "emoji" => Some(Self::Emoji),
+            "gif" => Some(Self::Gif),
+            "photo" => Some(Self::Photo),
+            "sticker" => Some(Self::Sticker),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Static => "static",
+            Self::Animated => "animated",
+            Self::Video => "video",
+            Self::Emoji => "emoji",
+            Self::Gif => "gif",
+            Self::Photo => "photo",
+            Self::Sticker => "sticker",
+        }
+    }
+
+    /// Condition on `sticker` rows keeping the stickers of this type
+    fn condition(&self) -> Condition {
+        match self {
+            Self::Static => Condition::all()
+                .add(model::sticker::Column::IsAnimated.eq(false))
+                .add(model::sticker::Column::IsVideo.eq(false)),
+            Self::Animated => Condition::any()
+                .add(model::sticker::Column::IsAnimated.eq(true))
+                .add(model::sticker::Column::IsVideo.eq(true)),
+            Self::Video => Condition::all().add(model::sticker::Column::IsVideo.eq(true)),
+            Self::Emoji => {
+                Condition::all().add(model::sticker::Column::Kind.eq(model::sticker::CUSTOM_EMOJI))
+            }
+            Self::Gif => {
+                Condition::all().add(model::sticker::Column::Kind.eq(model::sticker::ANIMATION))
+            }
+            Self::Photo => {
+                Condition::all().add(model::sticker::Column::Kind.eq(model::sticker::PHOTO))
+            }
+            Self::Sticker => Condition::all().add(model::sticker::Column::Kind.is_in([
+                model::sticker::REGULAR,
+                model::sticker::MASK,
+                model::sticker::CUSTOM_EMOJI,
+            ])),
+        }
+    }
+}
+
+/// Opening and closing quotes; the curly ones are inserted by some keyboards on their own
+const QUOTES: [char; 3] = ['"', '\u{201c}', '\u{201d}'];
+
+/// Splits `text` into whitespace-separated terms, keeping the words between double quotes
+/// together as one term, with the whitespace within collapsed into single spaces
+///
+/// An unterminated quote extends to the end of the text. Quotes may start in the middle of a
+/// term, so that `-"bad cat"` becomes `-bad cat`.
+pub fn split_terms(text: &str) -> Vec<String> {
+    let mut terms = vec![];
+    let mut term = String::new();
+    let mut quoted = false;
+    for c in text.chars() {
+        if QUOTES.contains(&c) {
+            quoted = !quoted;
+        } else if c.is_whitespace() {
+            if quoted {
+                if term.is_empty() == false && term.ends_with(' ') == false {
+                    term.push(' ');
+                }
+            } else if term.is_empty() == false {
+                terms.push(std::mem::take(&mut term).trim_end().to_owned());
+            }
+        } else {
+            term.push(c);
+        }
+    }
+    let term = term.trim_end();
+    if term.is_empty() == false {
+        terms.push(term.to_owned());
+    }
+    terms
+}
+
+/// Quotes `term` if it's a phrase, so that [`split_terms`] gives it back in one piece
+pub fn quote_term(term: &str) -> String {
+    if term.contains(char::is_whitespace) {
+        format!("\"{term}\"")
+    } else {
+        term.to_owned()
+    }
+}
+
+/// Longest namespace of a tag, so that longer words before a colon are taken as part of the tag
+const NAMESPACE_MAX_LEN: usize = 16;
+
+/// Splits a tag of the form `namespace:value` into its namespace and value, e.g. `char:miku` into
+/// `char` and `miku`. Tags without a namespace are returned whole as the value.
+pub fn split_namespace(tag: &str) -> (Option<&str>, &str) {
+    match tag.split_once(':') {
+        Some((namespace, value)) if is_namespace(namespace) && value.is_empty() == false => {
+            (Some(namespace), value)
+        }
+        _ => (None, tag),
+    }
+}
+
+/// Namespace of a tag, to be stored along with it
+pub fn tag_namespace(tag: &str) -> Option<String> {
+    split_namespace(tag).0.map(str::to_owned)
+}
+
+/// Whether `name` can be a namespace. Two-letter language codes can't, as they give the language
+/// of a tag instead, e.g. `zh:哭`.
+fn is_namespace(name: &str) -> bool {
+    language::is_language_code(name) == false
+        && (1..=NAMESPACE_MAX_LEN).contains(&name.chars().count())
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Inverse of [`split_terms`]
+pub fn join_terms<S: AsRef<str>>(terms: &[S]) -> String {
+    terms.iter().map(|term| quote_term(term.as_ref())).join(" ")
+}
+
+/// Length of the overlapping character n-grams that CJK text is split into
+const CJK_NGRAM_LEN: usize = 2;
+
+/// Splits a search term into its parts, which a sticker must all match to match the term, and
+/// each part into the tokens matched against tags
+///
+/// Words aren't separated by spaces in Chinese or Japanese, so a term like `貓咪哭哭` can't be
+/// expected to be a substring of any single tag. Runs of CJK characters longer than an n-gram are
+/// split into overlapping bigrams instead, e.g. `貓咪`, `咪哭` and `哭哭`, which a tag contains
+/// exactly when they're among its own bigrams. The other parts of the term are single tokens, and
+/// terms without such runs are a single part.
+pub fn term_tokens(term: &str) -> Vec<Vec<String>> {
+    let runs = term
+        .chars()
+        .group_by(|&c| language::is_cjk(c))
+        .into_iter()
+        .map(|(is_cjk, run)| (is_cjk, run.collect_vec()))
+        .collect_vec();
+    if runs
+        .iter()
+        .all(|(is_cjk, run)| *is_cjk == false || run.len() <= CJK_NGRAM_LEN)
+    {
+        return vec![vec![term.to_owned()]];
+    }
+
+    let mut parts = vec![];
+    for (is_cjk, run) in runs {
+        if is_cjk && run.len() > CJK_NGRAM_LEN {
+            parts.push(run.windows(CJK_NGRAM_LEN).map(String::from_iter).collect());
+        } else {
+            let token = String::from_iter(run);
+            let token = token.trim();
+            if token.is_empty() == false {
+                parts.push(vec![token.to_owned()]);
+            }
+        }
+    }
+    parts
+}
+
+/// Whether the tokens `matched` by the tags of a sticker match every part of a term split by
+/// [`term_tokens`]
+///
+/// The n-grams of a part spanning the boundaries of the words in the tags, e.g. `咪哭` for the
+/// tags `貓咪` and `哭哭`, needn't match, as long as the n-grams around them cover every character.
+pub fn matches_term(parts: &[Vec<String>], matched: &HashSet<&str>) -> bool {
+    let is_matched = |token: &String| matched.contains(token.as_str());
+    parts.iter().all(|tokens| {
+        tokens.first().map_or(false, is_matched)
+            && tokens.last().map_or(false, is_matched)
+            && tokens
+                .windows(2)
+                .all(|pair| is_matched(&pair[0]) || is_matched(&pair[1]))
+    })
+}
+
+/// Inline query split into its terms
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// Terms matched against the tags (and emoji, and recognized text) of the stickers
+    pub terms: Vec<String>,
+
+    /// Terms that must not be contained in any of the tags of the stickers
+    pub excluded: Vec<String>,
+
+    /// Partial sticker set names, given with the `set:` prefix
+    pub sets: Vec<String>,
+
+    /// Sticker types, given with the `type:` prefix
+    pub types: Vec<StickerType>,
+
+    /// Tag namespaces, given as terms ending with a colon
+    pub namespaces: Vec<String>,
+
+    /// Id of the sticker to find similar stickers to, given with the `~` prefix
+    pub similar_to: Option<i32>,
+
+    /// Whether only the favorites of the user are wanted, given with the `fav:` term
+    pub favorites: bool,
+
+    /// Whether only the stickers recently chosen by the user are wanted, given with the `recent:`
+    /// term
+    pub recent: bool,
+
+    /// Group chats whose scoped tags the querying user may search, which are looked up for the
+    /// user rather than given in the query
+    pub chats: Vec<i64>,
+
+    /// Tagger id of the querying user, whose private tags they may search
+    pub tagger_id: Option<i32>,
+
+    /// Order of the search results, which is set for the querying user rather than given in the
+    /// query
+    pub sort_order: SortOrder,
+
+    /// Whether the stickers chosen by the querying user before are ranked first and shown when
+    /// browsing, which is likewise set for the user
+    pub personalized: bool,
+
+    /// Number of the best matches wanted, which is set for the page of the inline query rather
+    /// than given in it, or all of them if `None`
+    pub window: Option<usize>,
+
+    /// Whether the stickers tagged [`NSFW_TAG`] are wanted despite safe search, given with the
+    /// `nsfw:` term
+    pub nsfw: bool,
+}
+
+impl SearchQuery {
+    pub fn parse(query: &str) -> Self {
+        let mut search_query = Self::default();
+        for word in split_terms(query).iter().unique() {
+            if word == FAVORITES_TERM {
+                search_query.favorites = true;
+                continue;
+            }
+            if word == RECENT_TERM {
+                search_query.recent = true;
+                continue;
+            }
+            if word == NSFW_TERM {
+                search_query.nsfw = true;
+                continue;
+            }
+            if let Some(set) = word.strip_prefix(SET_PREFIX) {
+                if set.is_empty() == false {
+                    search_query.sets.push(set.to_owned());
+                }
+                continue;
+            }
+            if let Some(namespace) = word.strip_suffix(':').filter(|name| is_namespace(name)) {
+                search_query.namespaces.push(namespace.to_owned());
+                continue;
+            }
+            // unknown types are searched for like any other term
+            if let Some(sticker_type) = word.strip_prefix(TYPE_PREFIX).and_then(StickerType::parse)
+            {
+                if search_query.types.contains(&sticker_type) == false {
+                    search_query.types.push(sticker_type);
+                }
+                continue;
+            }
+            // as is anything but a sticker id after the similarity prefix
+            if let Some(sticker_id) = word
+                .strip_prefix(SIMILAR_PREFIX)
+                .and_then(|sticker_id| sticker_id.parse().ok())
+            {
+                search_query.similar_to = Some(sticker_id);
+                continue;
+            }
+            match word.strip_prefix('-') {
+                Some(excluded) if excluded.is_empty() == false => {
+                    search_query.excluded.push(excluded.to_owned())
+                }
+                _ => search_query.terms.push(word.clone()),
+            }
+        }
+        search_query
+    }
+
+    /// Drops the terms that would match most of the index without telling much, before they're
+    /// searched for: single letters or digits, and the stop words of the language of the term,
+    /// which is the `language` of the user unless the script of the term tells
+    pub fn drop_noise(&mut self, language: Option<&str>) {
+        self.terms.retain(|term| {
+            let mut chars = term.chars();
+            let single_letter = match (chars.next(), chars.next()) {
+                (Some(c), None) => c.is_alphanumeric() && language::is_cjk(c) == false,
+                _ => false,
+            };
+            let term_language = language::detect(term).or(language);
+            let stop_word = term_language.map_or(false, |term_language| {
+                language::is_stop_word(term, term_language)
+            });
+            single_letter == false && stop_word == false
+        });
+    }
+
+    /// Hides the stickers tagged [`NSFW_TAG`] if safe search is on, unless the query asks for them
+    /// with `nsfw:`
+    pub fn apply_safe_search(&mut self, safe_search: bool) {
+        if safe_search
+            && self.nsfw == false
+            && self.excluded.iter().any(|term| term == NSFW_TAG) == false
+        {
+            self.excluded.push(NSFW_TAG.to_owned());
+        }
+    }
+
+    /// Whether the query has nothing to search for; excluded terms alone match nothing
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Whether the query names sets or tag namespaces, whose stickers are listed if there's
+    /// nothing else to search for
+    pub fn has_filters(&self) -> bool {
+        self.sets.is_empty() == false || self.namespaces.is_empty() == false
+    }
+
+    /// Normalized form of the query, used to key the query cache
+    pub fn cache_key(&self) -> String {
+        self.terms
+            .iter()
+            .map(|term| quote_term(term))
+            .chain(
+                self.excluded
+                    .iter()
+                    .map(|term| format!("-{}", quote_term(term))),
+            )
+            .chain(
+                self.sets
+                    .iter()
+                    .map(|set| format!("{SET_PREFIX}{}", quote_term(set))),
+            )
+            .chain(
+                self.types
+                    .iter()
+                    .map(|sticker_type| format!("{TYPE_PREFIX}{}", sticker_type.as_str())),
+            )
+            .chain(
+                self.namespaces
+                    .iter()
+                    .map(|namespace| format!("{namespace}:")),
+            )
+            .chain(
+                self.similar_to
+                    .map(|sticker_id| format!("{SIMILAR_PREFIX}{sticker_id}")),
+            )
+            .chain(self.favorites.then(|| FAVORITES_TERM.to_owned()))
+            .chain(self.recent.then(|| RECENT_TERM.to_owned()))
+            // terms never contain line breaks, so the chats can't be mistaken for one
+            .chain(
+                (self.chats.is_empty() == false)
+                    .then(|| format!("\nchats:{}", self.chats.iter().join(","))),
+            )
+            .chain(
+                self.tagger_id
+                    .map(|tagger_id| format!("\ntagger:{tagger_id}")),
+            )
+            .join(" ")
+    }
+
+    /// Condition on `tagged_sticker` rows keeping the tags the querying user may search: the ones
+    /// searchable by everyone, the ones scoped to the chats in the query, and their own private
+    /// ones, as long as they haven't been removed
+    pub fn scope_condition(&self) -> SimpleExpr {
+        let visible = model::tagged_sticker::Column::Visibility.eq(model::tagged_sticker::PUBLIC);
+        let visible = match self.tagger_id {
+            Some(tagger_id) => visible.or(model::tagged_sticker::Column::TaggerId.eq(tagger_id)),
+            None => visible,
+        };
+        model::tagged_sticker::Column::ChatId
+            .is_null()
+            .or(model::tagged_sticker::Column::ChatId.is_in(self.chats.clone()))
+            .and(visible)
+            .and(model::tagged_sticker::Column::DeletedAt.is_null())
+    }
+
+    /// Condition on `sticker` rows keeping the stickers from the sets named in the query, if any
+    pub fn set_condition(&self) -> Condition {
+        // an empty `any` condition matches nothing
+        if self.sets.is_empty() {
+            return Condition::all();
+        }
+        let mut condition = Condition::any();
+        for set in self.sets.iter() {
+            condition = condition.add(model::sticker::Column::SetName.contains(set));
+        }
+        condition
+    }
+
+    /// Condition on `sticker` rows keeping the stickers of any of the types named in the query, if
+    /// any
+    pub fn type_condition(&self) -> Condition {
+        // an empty `any` condition matches nothing
+        if self.types.is_empty() {
+            return Condition::all();
+        }
+        let mut condition = Condition::any();
+        for sticker_type in self.types.iter() {
+            condition = condition.add(sticker_type.condition());
+        }
+        condition
+    }
+
+    /// Condition on `sticker` rows keeping the stickers with an approved tag in any of the
+    /// namespaces named in the query, if any
+    pub fn namespace_condition(&self) -> Condition {
+        if self.namespaces.is_empty() {
+            return Condition::all();
+        }
+        let tagged_in_namespace = Query::select()
+            .expr(Expr::val(1))
+            .from(model::tagged_sticker::Entity)
+            .and_where(
+                Expr::col((
+                    model::tagged_sticker::Entity,
+                    model::tagged_sticker::Column::StickerId,
+                ))
+                .equals((model::sticker::Entity, model::sticker::Column::Id)),
+            )
+            .and_where(model::tagged_sticker::Column::Namespace.is_in(self.namespaces.clone()))
+            .and_where(model::tagged_sticker::Column::Approved.eq(true))
+            .and_where(self.scope_condition())
+            .to_owned();
+        Condition::all().add(Expr::exists(tagged_in_namespace))
+    }
+
+    /// Condition on `sticker` rows rejecting the stickers tagged with any of the excluded terms
+    pub fn exclusion_condition(&self) -> Condition {
+        let mut condition = Condition::all();
+        for term in self.excluded.iter() {
+            let tagged_with_term = Query::select()
+                .expr(Expr::val(1))
+                .from(model::tagged_sticker::Entity)
+                .and_where(
+                    Expr::col((
+                        model::tagged_sticker::Entity,
+                        model::tagged_sticker::Column::StickerId,
+                    ))
+                    .equals((model::sticker::Entity, model::sticker::Column::Id)),
+                )
+                .and_where(model::tagged_sticker::Column::Tag.contains(term))
+                .and_where(model::tagged_sticker::Column::Approved.eq(true))
+                .and_where(self.scope_condition())
+                .to_owned();
+            condition = condition.add(Expr::exists(tagged_with_term).not());
+        }
+        condition
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_terms_keeps_quoted_phrases_together() {
+        assert_eq!(
+            split_terms("cat  \"good   morning\" -\"bad cat\""),
+            ["cat", "good morning", "-bad cat"]
+        );
+        assert_eq!(split_terms("\u{201c}good morning"), ["good morning"]);
+        assert!(split_terms("  \"\" ").is_empty());
+    }
+
+    #[test]
+    fn join_terms_is_inverse_of_split_terms() {
+        let terms = ["cat", "good morning"];
+        assert_eq!(join_terms(&terms), "cat \"good morning\"");
+        assert_eq!(split_terms(&join_terms(&terms)), terms);
+    }
+
+    #[test]
+    fn parse_sorts_terms_by_kind() {
+        let query =
+            SearchQuery::parse("cat -dog set:pack type:static type:emoji char: ~12 fav: recent:");
+        assert_eq!(
+            query,
+            SearchQuery {
+                terms: vec!["cat".to_owned()],
+                excluded: vec!["dog".to_owned()],
+                sets: vec!["pack".to_owned()],
+                types: vec![StickerType::Static, StickerType::Emoji],
+                namespaces: vec!["char".to_owned()],
+                similar_to: Some(12),
+                favorites: true,
+                recent: true,
+                chats: vec![],
+                tagger_id: None,
+                sort_order: SortOrder::Relevance,
+                personalized: false,
+                window: None,
+                nsfw: false,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_searches_unknown_fields_as_terms() {
+        let query = SearchQuery::parse("type:foo ~bar - zh:");
+        assert_eq!(query.terms, ["type:foo", "~bar", "-", "zh:"]);
+        assert!(query.types.is_empty());
+        assert!(query.namespaces.is_empty());
+        assert_eq!(query.similar_to, None);
+    }
+
+    #[test]
+    fn split_namespace_leaves_language_codes_in_the_tag() {
+        assert_eq!(split_namespace("char:miku"), (Some("char"), "miku"));
+        assert_eq!(split_namespace("zh:哭"), (None, "zh:哭"));
+        assert_eq!(split_namespace("char:"), (None, "char:"));
+        assert_eq!(split_namespace("good morning:x"), (None, "good morning:x"));
+        assert_eq!(tag_namespace("mood:angry").as_deref(), Some("mood"));
+    }
+
+    #[test]
+    fn term_tokens_splits_cjk_runs_into_bigrams() {
+        assert_eq!(term_tokens("cat"), [["cat"]]);
+        assert_eq!(term_tokens("貓咪"), [["貓咪"]]);
+        assert_eq!(term_tokens("貓咪哭哭"), [["貓咪", "咪哭", "哭哭"]]);
+        assert_eq!(term_tokens("哈哈哈"), [["哈哈", "哈哈"]]);
+        assert_eq!(term_tokens("ok貓咪哭"), [vec!["ok"], vec!["貓咪", "咪哭"]]);
+    }
+
+    #[test]
+    fn matches_term_needs_every_character_covered() {
+        let parts = term_tokens("貓咪哭哭");
+        let matches = |matched: &[&str]| matches_term(&parts, &matched.iter().copied().collect());
+        assert!(matches(&["貓咪", "咪哭", "哭哭"]));
+        assert!(matches(&["貓咪", "哭哭"]));
+        assert!(matches(&["哭哭"]) == false);
+        assert!(matches(&["貓咪", "咪哭"]) == false);
+
+        let parts = term_tokens("ok貓咪哭");
+        let matches = |matched: &[&str]| matches_term(&parts, &matched.iter().copied().collect());
+        assert!(matches(&["ok", "貓咪", "咪哭"]));
+        assert!(matches(&["貓咪", "咪哭"]) == false);
+    }
+
+    #[test]
+    fn drop_noise_drops_single_letters_and_stop_words() {
+        let mut query = SearchQuery::parse("a cat The 哭 的 7");
+        query.drop_noise(Some("en"));
+        assert_eq!(query.terms, ["cat", "哭"]);
+
+        // latin words are only stop words in the language of the user
+        let mut query = SearchQuery::parse("the die");
+        query.drop_noise(Some("de"));
+        assert_eq!(query.terms, ["the"]);
+        let mut query = SearchQuery::parse("the die");
+        query.drop_noise(None);
+        assert_eq!(query.terms, ["the", "die"]);
+    }
+
+    #[test]
+    fn safe_search_excludes_the_nsfw_tag_unless_asked_for() {
+        let mut query = SearchQuery::parse("cat -nsfw");
+        query.apply_safe_search(true);
+        assert_eq!(query.excluded, [NSFW_TAG]);
+
+        let mut query = SearchQuery::parse("cat");
+        query.apply_safe_search(true);
+        assert_eq!(query, SearchQuery::parse("cat -nsfw"));
+
+        let mut query = SearchQuery::parse("cat nsfw:");
+        query.apply_safe_search(true);
+        assert!(query.nsfw);
+        assert!(query.excluded.is_empty());
+        assert!(query.namespaces.is_empty());
+
+        let mut query = SearchQuery::parse("cat");
+        query.apply_safe_search(false);
+        assert!(query.excluded.is_empty());
+    }
+
+    #[test]
+    fn cache_key_is_normalized() {
+        let key = |query: &str| SearchQuery::parse(query).cache_key();
+        assert_eq!(key("cat  dog"), key("cat dog"));
+        assert_eq!(key("cat cat"), key("cat"));
+        assert_ne!(key("cat -dog"), key("cat dog"));
+        assert_ne!(key("cat fav:"), key("cat"));
+        assert_eq!(key("\"good morning\""), "\"good morning\"");
+
+        let mut scoped = SearchQuery::parse("cat");
+        scoped.chats = vec![-100];
+        assert_ne!(scoped.cache_key(), key("cat chats:-100"));
+        assert_ne!(scoped.cache_key(), key("cat"));
+
+        let mut private = SearchQuery::parse("cat");
+        private.tagger_id = Some(1);
+        assert_ne!(private.cache_key(), key("cat"));
+    }
+}
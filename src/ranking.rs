@@ -0,0 +1,448 @@
+//! Ranking of inline query results
+//!
+//! Stickers that the querying user has chosen before are boosted to the top. The rest are ranked
+//! by whether their matched tags are in the language of the user first, and then by their
+//! [`relevance`] to the query, which adds up the number of query terms matched, how well the tags
+//! match the words of the query, their popularity, and who tagged them:
+//!
+//! - each term matched weighs more than the rest of the score, so the stickers matching more of
+//!   the terms come first
+//! - tags that are a word of the query weigh more than the ones merely containing it, and tags on
+//!   fewer stickers weigh more than common ones, see [`match_score`]
+//! - the popularity only counts by its logarithm, so that a generically popular sticker doesn't
+//!   bury better matches
+//! - so does the [`crate::reputation`] of the taggers whose tags match, which counts against the
+//!   stickers only tagged by taggers whose tags are often removed, and the balance of the votes
+//!   for and against the matching tags
+//!
+//! The popularity decays exponentially with the time since the sticker was last chosen, so that
+//! stickers that were popular a long time ago don't dominate the results forever.
+//!
+//! Users may instead have the results sorted by popularity or recency alone, see [`SortOrder`].
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::model;
+
+/// Time it takes for the popularity of an unused sticker to decay to half
+const POPULARITY_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Weight of each query term matched by a sticker, which outweighs the rest of its relevance
+const TERM_WEIGHT: f64 = 10.0;
+/// Weights of a tag that is a word of the query, and of one merely containing it
+const EXACT_MATCH_WEIGHT: f64 = 3.0;
+const PARTIAL_MATCH_WEIGHT: f64 = 1.0;
+/// Weight of the logarithm of the decayed popularity
+const POPULARITY_WEIGHT: f64 = 0.2;
+/// Weight of the logarithm of the reputation of the taggers, which is negative for bad taggers
+const REPUTATION_WEIGHT: f64 = 0.3;
+/// Weight of the logarithm of the votes on the tags besides the one of their tagger, which are
+/// negative for tags voted against
+const VOTE_WEIGHT: f64 = 0.5;
+
+/// Popularity of a sticker at time `now`, decayed by the time since it was last used
+pub fn decayed_popularity(
+    popularity: i64,
+    last_used: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> f64 {
+    // stickers that have never been chosen have no popularity to decay
+    let last_used = match last_used {
+        Some(t) => t,
+        None => return popularity as f64,
+    };
+
+    let age_days = (now - last_used).num_seconds().max(0) as f64 / (24.0 * 60.0 * 60.0);
+    popularity as f64 * 0.5f64.powf(age_days / POPULARITY_HALF_LIFE_DAYS)
+}
+
+/// Score of a tag (or emoji, or recognized text) matching a word of the query, which is higher if
+/// the tag is the word itself, and lower the more stickers share the tag
+pub fn match_score(exact: bool, frequency: usize) -> f64 {
+    let weight = if exact {
+        EXACT_MATCH_WEIGHT
+    } else {
+        PARTIAL_MATCH_WEIGHT
+    };
+    weight / (1.0 + (frequency.max(1) as f64).ln())
+}
+
+/// Query-specific information used for ranking, besides what's stored on the stickers
+#[derive(Debug, Default)]
+pub struct Signals {
+    /// Number of query terms matched, keyed by sticker id
+    pub terms_matched: HashMap<i32, usize>,
+
+    /// Sum of the best [`match_score`] for each word of the query, keyed by sticker id
+    pub match_score: HashMap<i32, f64>,
+
+    /// Best reputation of the taggers of the tags matching the query, keyed by sticker id
+    pub reputation: HashMap<i32, f64>,
+
+    /// Best balance of the votes on the tags matching the query, which is 1 for the tags that
+    /// only their tagger voted for, keyed by sticker id
+    pub vote_balance: HashMap<i32, i64>,
+
+    /// Number of times the querying user has chosen the sticker before, keyed by sticker id
+    pub times_chosen: HashMap<i32, i64>,
+
+    /// Stickers with tags matching the query in the language of the querying user
+    pub in_language: HashSet<i32>,
+}
+
+/// Order of the search results, set for the bot with `default_sort_order` and per user with
+/// /settings
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// As ranked by [`rank`]
+    #[default]
+    Relevance,
+    /// Most popular first, by the decayed popularity
+    Popularity,
+    /// Most recently indexed first
+    Recency,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 3] = [Self::Relevance, Self::Popularity, Self::Recency];
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "relevance" => Some(Self::Relevance),
+            "popularity" => Some(Self::Popularity),
+            "recency" => Some(Self::Recency),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Relevance => "relevance",
+            Self::Popularity => "popularity",
+            Self::Recency => "recency",
+        }
+    }
+}
+
+/// Sorts the stickers in the given order
+pub fn sort(
+    mut stickers: Vec<model::sticker::Model>,
+    order: SortOrder,
+    signals: &Signals,
+    now: DateTime<Utc>,
+) -> Vec<model::sticker::Model> {
+    match order {
+        SortOrder::Relevance => return rank(stickers, signals, now),
+        SortOrder::Popularity => stickers.sort_by(|a, b| {
+            decayed_popularity(b.popularity, b.last_used, now)
+                .partial_cmp(&decayed_popularity(a.popularity, a.last_used, now))
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.id.cmp(&b.id))
+        }),
+        // stickers indexed before the time was recorded count as the oldest
+        SortOrder::Recency => stickers.sort_by(|a, b| {
+            b.indexed_at
+                .cmp(&a.indexed_at)
+                .then_with(|| b.id.cmp(&a.id))
+        }),
+    }
+    stickers
+}
+
+/// Sorts the stickers from the most to the least relevant
+pub fn rank(
+    mut stickers: Vec<model::sticker::Model>,
+    signals: &Signals,
+    now: DateTime<Utc>,
+) -> Vec<model::sticker::Model> {
+    let times_chosen = |sticker: &model::sticker::Model| {
+        signals.times_chosen.get(&sticker.id).copied().unwrap_or(0)
+    };
+    let in_language = |sticker: &model::sticker::Model| signals.in_language.contains(&sticker.id);
+
+    stickers.sort_by(|a, b| {
+        times_chosen(b)
+            .cmp(&times_chosen(a))
+            .then_with(|| in_language(b).cmp(&in_language(a)))
+            .then_with(|| {
+                relevance(b, signals, now)
+                    .partial_cmp(&relevance(a, signals, now))
+                    .unwrap_or(Ordering::Equal)
+            })
+            // tie-break on ids to keep the order stable across pages
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    stickers
+}
+
+/// Relevance score of a sticker to the query, combining the number of terms it matches, how well
+/// its tags match, its popularity at time `now`, and the reputation of its taggers and the votes
+/// on its tags
+pub fn relevance(sticker: &model::sticker::Model, signals: &Signals, now: DateTime<Utc>) -> f64 {
+    score(
+        signals.terms_matched.get(&sticker.id).copied().unwrap_or(0),
+        signals.match_score.get(&sticker.id).copied().unwrap_or(0.0),
+        decayed_popularity(sticker.popularity, sticker.last_used, now),
+        signals.reputation.get(&sticker.id).copied().unwrap_or(0.0),
+        signals.vote_balance.get(&sticker.id).copied().unwrap_or(1),
+    )
+}
+
+/// Relevance score of a sticker with the given signals, which only grows with each of them
+pub fn score(
+    terms_matched: usize,
+    match_score: f64,
+    popularity: f64,
+    reputation: f64,
+    vote_balance: i64,
+) -> f64 {
+    let votes = vote_balance - 1;
+    terms_matched as f64 * TERM_WEIGHT
+        + match_score
+        + POPULARITY_WEIGHT * popularity.max(0.0).ln_1p()
+        + REPUTATION_WEIGHT * signed_ln_1p(reputation)
+        + VOTE_WEIGHT * signed_ln_1p(votes as f64)
+}
+
+/// Bounds on the stickers that weren't ranked, which the database picked after `last` in the
+/// order they're sorted in, by the number of query words they match first for [`rank`]
+#[derive(Debug)]
+pub struct Bound {
+    /// Last of the stickers picked
+    pub last: model::sticker::Model,
+    /// Most times the querying user has chosen any of them before
+    pub times_chosen: i64,
+    /// Whether any of them has tags matching the query in the language of the querying user
+    pub in_language: bool,
+    /// Highest [`relevance`] of any of them
+    pub relevance: f64,
+}
+
+/// Whether the sticker comes before every sticker within the bound in the given order, so that
+/// ranking those as well wouldn't move it
+pub fn beats(
+    sticker: &model::sticker::Model,
+    order: SortOrder,
+    signals: &Signals,
+    bound: &Bound,
+    now: DateTime<Utc>,
+) -> bool {
+    match order {
+        SortOrder::Relevance => {
+            let times_chosen = signals.times_chosen.get(&sticker.id).copied().unwrap_or(0);
+            let in_language = signals.in_language.contains(&sticker.id);
+            (times_chosen, in_language)
+                .cmp(&(bound.times_chosen, bound.in_language))
+                .then_with(|| {
+                    relevance(sticker, signals, now)
+                        .partial_cmp(&bound.relevance)
+                        .unwrap_or(Ordering::Equal)
+                })
+                == Ordering::Greater
+        }
+        // the stored popularity of the stickers picked later is no higher than the last one's,
+        // and their decayed popularity no higher than that
+        SortOrder::Popularity => {
+            let popularity = decayed_popularity(sticker.popularity, sticker.last_used, now);
+            let last_popularity = bound.last.popularity as f64;
+            popularity > last_popularity
+                || (popularity == last_popularity && sticker.id <= bound.last.id)
+        }
+        // which is picked in the very order the stickers are sorted in
+        SortOrder::Recency => {
+            (sticker.indexed_at, sticker.id) >= (bound.last.indexed_at, bound.last.id)
+        }
+    }
+}
+
+/// Logarithm of the magnitude of `x`, keeping its sign
+fn signed_ln_1p(x: f64) -> f64 {
+    x.signum() * x.abs().ln_1p()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn sticker(
+        id: i32,
+        popularity: i64,
+        last_used: Option<DateTime<Utc>>,
+    ) -> model::sticker::Model {
+        model::sticker::Model {
+            id,
+            file_unique_id: format!("unique{id}"),
+            file_id: format!("file{id}"),
+            set_name: "set".to_owned(),
+            popularity,
+            emoji: None,
+            last_used,
+            dead: false,
+            indexed_at: None,
+            is_animated: false,
+            is_video: false,
+            width: None,
+            height: None,
+            thumb_file_id: None,
+            kind: model::sticker::REGULAR.to_owned(),
+        }
+    }
+
+    fn ids(stickers: &[model::sticker::Model]) -> Vec<i32> {
+        stickers.iter().map(|sticker| sticker.id).collect()
+    }
+
+    #[test]
+    fn popularity_halves_every_half_life() {
+        let now = Utc::now();
+        assert_eq!(decayed_popularity(100, None, now), 100.0);
+        assert_eq!(decayed_popularity(100, Some(now), now), 100.0);
+        let decayed = decayed_popularity(100, Some(now - Duration::days(30)), now);
+        assert!((decayed - 50.0).abs() < 1e-6);
+        // clocks going backwards don't boost anything
+        assert_eq!(
+            decayed_popularity(100, Some(now + Duration::days(1)), now),
+            100.0
+        );
+    }
+
+    #[test]
+    fn rank_orders_by_signals_then_popularity() {
+        let now = Utc::now();
+        let stickers = vec![
+            sticker(1, 1000, Some(now)),
+            sticker(2, 0, None),
+            sticker(3, 0, None),
+            sticker(4, 5, None),
+            sticker(5, 0, None),
+        ];
+        let signals = Signals {
+            terms_matched: HashMap::from([(3, 2)]),
+            times_chosen: HashMap::from([(5, 1)]),
+            in_language: HashSet::from([2]),
+            ..Default::default()
+        };
+        assert_eq!(ids(&rank(stickers, &signals, now)), [5, 2, 3, 1, 4]);
+    }
+
+    #[test]
+    fn rank_puts_rare_exact_matches_above_popular_stickers() {
+        let now = Utc::now();
+        let stickers = vec![sticker(1, 1000, Some(now)), sticker(2, 0, None)];
+        // the popular sticker has one of the many tags containing the word, the other sticker the
+        // only tag that is the word
+        let signals = Signals {
+            terms_matched: HashMap::from([(1, 1), (2, 1)]),
+            match_score: HashMap::from([(1, match_score(false, 50)), (2, match_score(true, 1))]),
+            ..Default::default()
+        };
+        assert_eq!(ids(&rank(stickers, &signals, now)), [2, 1]);
+
+        assert!(match_score(true, 1) > match_score(true, 2));
+        assert!(match_score(true, 2) > match_score(false, 2));
+    }
+
+    #[test]
+    fn rank_prefers_stickers_of_reputable_taggers() {
+        let now = Utc::now();
+        let stickers = vec![
+            sticker(1, 0, None),
+            sticker(2, 0, None),
+            sticker(3, 0, None),
+        ];
+        let signals = Signals {
+            terms_matched: HashMap::from([(1, 1), (2, 1), (3, 1)]),
+            reputation: HashMap::from([(1, -20.0), (3, 100.0)]),
+            ..Default::default()
+        };
+        assert_eq!(ids(&rank(stickers, &signals, now)), [3, 2, 1]);
+    }
+
+    #[test]
+    fn rank_prefers_tags_voted_for() {
+        let now = Utc::now();
+        let stickers = vec![
+            sticker(1, 0, None),
+            sticker(2, 0, None),
+            sticker(3, 0, None),
+        ];
+        let signals = Signals {
+            terms_matched: HashMap::from([(1, 1), (2, 1), (3, 1)]),
+            vote_balance: HashMap::from([(1, -1), (2, 1), (3, 4)]),
+            ..Default::default()
+        };
+        assert_eq!(ids(&rank(stickers, &signals, now)), [3, 2, 1]);
+    }
+
+    #[test]
+    fn rank_prefers_recent_popularity() {
+        let now = Utc::now();
+        let stickers = vec![
+            sticker(1, 100, Some(now - Duration::days(365))),
+            sticker(2, 10, Some(now)),
+            sticker(3, 10, Some(now)),
+        ];
+        assert_eq!(ids(&rank(stickers, &Signals::default(), now)), [2, 3, 1]);
+    }
+
+    #[test]
+    fn beats_only_what_the_bound_rules_out() {
+        let now = Utc::now();
+        let stickers = [sticker(1, 10, None), sticker(2, 5, None)];
+        let signals = Signals {
+            terms_matched: HashMap::from([(1, 1), (2, 1)]),
+            in_language: HashSet::from([2]),
+            ..Default::default()
+        };
+        let bound = Bound {
+            last: sticker(3, 5, None),
+            times_chosen: 0,
+            in_language: false,
+            relevance: relevance(&stickers[0], &signals, now),
+        };
+        let beats_bound = |sticker, order| beats(sticker, order, &signals, &bound, now);
+        // the bound may be as relevant as the first sticker, but not in the language of the user
+        assert_eq!(beats_bound(&stickers[0], SortOrder::Relevance), false);
+        assert!(beats_bound(&stickers[1], SortOrder::Relevance));
+        assert!(beats_bound(&stickers[0], SortOrder::Popularity));
+        // ties on popularity are broken by the ids
+        assert!(beats_bound(&stickers[1], SortOrder::Popularity));
+        let chosen = Bound {
+            times_chosen: 1,
+            ..bound
+        };
+        assert_eq!(
+            beats(&stickers[1], SortOrder::Relevance, &signals, &chosen, now),
+            false
+        );
+    }
+
+    #[test]
+    fn sort_ignores_signals_unless_by_relevance() {
+        let now = Utc::now();
+        let mut stickers = vec![
+            sticker(1, 5, None),
+            sticker(2, 100, Some(now)),
+            sticker(3, 0, None),
+        ];
+        stickers[0].indexed_at = Some(now - Duration::days(1));
+        stickers[2].indexed_at = Some(now);
+        let signals = Signals {
+            times_chosen: HashMap::from([(1, 3)]),
+            ..Default::default()
+        };
+
+        let sorted = |order| ids(&sort(stickers.clone(), order, &signals, now));
+        assert_eq!(sorted(SortOrder::Relevance), [1, 2, 3]);
+        assert_eq!(sorted(SortOrder::Popularity), [2, 1, 3]);
+        assert_eq!(sorted(SortOrder::Recency), [3, 1, 2]);
+    }
+}
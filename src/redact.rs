@@ -0,0 +1,127 @@
+//! Redaction of the secrets from the logs
+//!
+//! The admin commands carry the secret, and log lines may quote the text of messages, e.g. when
+//! handling one failed. The logs are written through [`writer`], which replaces the words that are
+//! one of the secrets with [`REDACTED`]. Only the hashes of the secrets are kept, so every word of
+//! a log line is hashed to compare it with them, once they're known from [`init`].
+
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    io::{self, Write},
+    sync::OnceLock,
+};
+
+use crate::auth;
+
+/// Stands in for the secrets in the logs
+const REDACTED: &str = "<redacted>";
+
+/// Characters that words are separated by besides whitespace, which quote the text of messages in
+/// log lines, plain or escaped as JSON
+const DELIMITERS: &[char] = &[
+    '"', '\'', '`', '\\', ',', ';', '(', ')', '[', ']', '{', '}', '=',
+];
+
+static SECRET_HASHES: OnceLock<HashSet<String>> = OnceLock::new();
+
+/// Starts redacting the secrets with the given hashes, see [`crate::config::Config::secret_hashes`]
+pub fn init(secret_hashes: Vec<String>) {
+    let _ = SECRET_HASHES.set(secret_hashes.into_iter().collect());
+}
+
+/// Writer of a log line to stdout, which is redacted once it's complete
+pub fn writer() -> RedactingWriter {
+    RedactingWriter { line: Vec::new() }
+}
+
+pub struct RedactingWriter {
+    line: Vec<u8>,
+}
+
+impl Write for RedactingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.line.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for RedactingWriter {
+    fn drop(&mut self) {
+        let line = String::from_utf8_lossy(&self.line);
+        let redacted = match SECRET_HASHES.get() {
+            Some(secret_hashes) => redact(&line, secret_hashes),
+            None => line,
+        };
+        // there's nowhere left to report failing to log to
+        let _ = io::stdout().write_all(redacted.as_bytes());
+    }
+}
+
+/// Text with the words that are one of the secrets replaced
+fn redact<'a>(text: &'a str, secret_hashes: &HashSet<String>) -> Cow<'a, str> {
+    if secret_hashes.is_empty() {
+        return Cow::Borrowed(text);
+    }
+
+    let mut redacted = String::with_capacity(text.len());
+    let mut found = false;
+    let mut word_start = 0;
+    // the end of the text ends the last word as well
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        if c.is_whitespace() == false && DELIMITERS.contains(&c) == false {
+            continue;
+        }
+        let word = &text[word_start..i];
+        if word.is_empty() == false && secret_hashes.contains(&auth::hash_token(word)) {
+            redacted.push_str(REDACTED);
+            found = true;
+        } else {
+            redacted.push_str(word);
+        }
+        if i < text.len() {
+            redacted.push(c);
+        }
+        word_start = i + c.len_utf8();
+    }
+
+    if found {
+        Cow::Owned(redacted)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_redacted_wherever_they_are_quoted() {
+        let secret_hashes: HashSet<String> = ["s3cret", "秘密"].map(auth::hash_token).into();
+
+        assert_eq!(
+            redact(
+                "Failed to handle message \"/allow s3cret someone\" from admin: timed out",
+                &secret_hashes
+            ),
+            "Failed to handle message \"/allow <redacted> someone\" from admin: timed out"
+        );
+        assert_eq!(
+            redact(
+                r#"{"text":"/export s3cret","secret":"秘密"}"#,
+                &secret_hashes
+            ),
+            r#"{"text":"/export <redacted>","secret":"<redacted>"}"#
+        );
+        assert!(matches!(
+            redact("no s3crets here", &secret_hashes),
+            Cow::Borrowed("no s3crets here")
+        ));
+        assert_eq!(redact("s3cret", &HashSet::new()), "s3cret");
+    }
+}
@@ -0,0 +1,181 @@
+//! Reputation of the taggers
+//!
+//! The reputation of a tagger adds up how their tags fare: each of their approved tags counts for
+//! a point, the stickers they tagged gain them a tenth of a point each time they're chosen, and
+//! each of their tags removed by someone else (e.g. for a report, or rejected by /review) costs
+//! them a few points. Removed tags stop counting once they're purged from the trash.
+//!
+//! Searches rank the stickers tagged by reputable taggers higher, from the reputations
+//! recomputed periodically by [`recompute`], and the tags of the taggers reaching the
+//! `trust_threshold` skip the review, from their current reputation.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use sea_orm::{
+    sea_query::{CaseStatement, Expr, SimpleExpr},
+    ColumnTrait, Condition, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect,
+    TransactionTrait,
+};
+use tracing::info;
+
+use crate::model;
+
+/// Points per approved tag, per time a tagged sticker was chosen, and per tag removed by others
+const APPROVED_TAG_WEIGHT: f64 = 1.0;
+const CHOICE_WEIGHT: f64 = 0.1;
+const REMOVED_TAG_PENALTY: f64 = 5.0;
+
+/// Number of taggers updated by each statement of [`recompute`]
+const RECOMPUTE_BATCH: usize = 500;
+
+/// Reputation of a tagger from the number of their approved tags, the times the stickers they
+/// tagged were chosen, and the number of their tags removed by someone else
+pub fn score(approved_tags: i64, times_chosen: i64, removed_tags: i64) -> f64 {
+    approved_tags as f64 * APPROVED_TAG_WEIGHT + times_chosen as f64 * CHOICE_WEIGHT
+        - removed_tags as f64 * REMOVED_TAG_PENALTY
+}
+
+/// Current reputations of the taggers with any tags, or only of the given one, keyed by the row
+/// id of the tagger
+pub async fn compute(
+    db: &impl ConnectionTrait,
+    tagger_id: Option<i32>,
+) -> Result<HashMap<i32, f64>, DbErr> {
+    let tagger_condition = Condition::all().add_option(
+        tagger_id.map(|tagger_id| model::tagged_sticker::Column::TaggerId.eq(tagger_id)),
+    );
+
+    let approved_tags: HashMap<i32, i64> = model::tagged_sticker::Entity::find()
+        .select_only()
+        .column(model::tagged_sticker::Column::TaggerId)
+        .column_as(model::tagged_sticker::Column::Id.count(), "tag_count")
+        .filter(tagger_condition.clone())
+        .filter(model::tagged_sticker::Column::Approved.eq(true))
+        .filter(model::tagged_sticker::Column::DeletedAt.is_null())
+        .group_by(model::tagged_sticker::Column::TaggerId)
+        .into_tuple::<(i32, i64)>()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+
+    // the popularity of the stickers counts their choices, once per tagger however many of
+    // their tags each sticker has
+    let tagged_sticker_popularity: Vec<(i32, i32, i64)> = model::tagged_sticker::Entity::find()
+        .select_only()
+        .column(model::tagged_sticker::Column::TaggerId)
+        .column(model::tagged_sticker::Column::StickerId)
+        .column(model::sticker::Column::Popularity)
+        .distinct()
+        .inner_join(model::sticker::Entity)
+        .filter(tagger_condition.clone())
+        .filter(model::tagged_sticker::Column::Approved.eq(true))
+        .filter(model::tagged_sticker::Column::DeletedAt.is_null())
+        .into_tuple()
+        .all(db)
+        .await?;
+    let mut times_chosen: HashMap<i32, i64> = HashMap::new();
+    for (tagger_id, _, popularity) in tagged_sticker_popularity {
+        *times_chosen.entry(tagger_id).or_default() += popularity;
+    }
+
+    // tags removed by the taggers themselves, e.g. to fix a typo, don't count against them
+    let removed_tags: HashMap<i32, i64> = model::tagged_sticker::Entity::find()
+        .select_only()
+        .column(model::tagged_sticker::Column::TaggerId)
+        .column_as(model::tagged_sticker::Column::Id.count(), "tag_count")
+        .inner_join(model::user::Entity)
+        .filter(tagger_condition)
+        .filter(model::tagged_sticker::Column::DeletedAt.is_not_null())
+        .filter(
+            model::tagged_sticker::Column::DeletedBy
+                .is_null()
+                .or(Expr::col((
+                    model::tagged_sticker::Entity,
+                    model::tagged_sticker::Column::DeletedBy,
+                ))
+                .ne(Expr::col((
+                    model::user::Entity,
+                    model::user::Column::UserId,
+                )))),
+        )
+        .group_by(model::tagged_sticker::Column::TaggerId)
+        .into_tuple::<(i32, i64)>()
+        .all(db)
+        .await?
+        .into_iter()
+        .collect();
+
+    let reputations = approved_tags
+        .keys()
+        .chain(times_chosen.keys())
+        .chain(removed_tags.keys())
+        .unique()
+        .map(|tagger_id| {
+            let reputation = score(
+                approved_tags.get(tagger_id).copied().unwrap_or_default(),
+                times_chosen.get(tagger_id).copied().unwrap_or_default(),
+                removed_tags.get(tagger_id).copied().unwrap_or_default(),
+            );
+            (*tagger_id, reputation)
+        })
+        .collect();
+    Ok(reputations)
+}
+
+/// Current reputation of a tagger
+pub async fn of_tagger(db: &impl ConnectionTrait, tagger_id: i32) -> Result<f64, DbErr> {
+    let reputations = compute(db, Some(tagger_id)).await?;
+    Ok(reputations.get(&tagger_id).copied().unwrap_or_default())
+}
+
+/// Recomputes the reputation of every tagger, which the searches rank the stickers by
+pub async fn recompute<C: ConnectionTrait + TransactionTrait>(db: &C) -> Result<(), DbErr> {
+    let reputations = compute(db, None).await?.into_iter().collect_vec();
+
+    let txn = db.begin().await?;
+    // the taggers without tags have no reputation left
+    model::user::Entity::update_many()
+        .col_expr(model::user::Column::Reputation, Expr::value(0.0))
+        .filter(model::user::Column::Reputation.ne(0.0))
+        .exec(&txn)
+        .await?;
+    for batch in reputations.chunks(RECOMPUTE_BATCH) {
+        let mut reputation_case = CaseStatement::new();
+        for &(tagger_id, reputation) in batch {
+            reputation_case =
+                reputation_case.case(model::user::Column::Id.eq(tagger_id), Expr::val(reputation));
+        }
+        let reputation_case = reputation_case.finally(Expr::col(model::user::Column::Reputation));
+
+        model::user::Entity::update_many()
+            .col_expr(
+                model::user::Column::Reputation,
+                SimpleExpr::from(reputation_case),
+            )
+            .filter(model::user::Column::Id.is_in(batch.iter().map(|&(tagger_id, _)| tagger_id)))
+            .exec(&txn)
+            .await?;
+    }
+    txn.commit().await?;
+
+    info!(
+        "Recomputed the reputation of {taggers} taggers",
+        taggers = reputations.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removed_tags_outweigh_approved_ones() {
+        assert_eq!(score(0, 0, 0), 0.0);
+        assert_eq!(score(10, 0, 0), 10.0);
+        assert_eq!(score(10, 50, 0), 15.0);
+        assert!(score(10, 0, 3) < 0.0);
+    }
+}
@@ -0,0 +1,294 @@
+//! Settings of users, picked with the buttons sent by /settings
+//!
+//! The settings are stored along with the other preferences of the user in `user_preference`,
+//! where the unset ones fall back on the defaults of the bot. The callback data of each button
+//! names a setting and the value it's changed to, e.g. `settings:sort:recency`, so that pressing
+//! it only changes that one setting of the presser.
+
+use itertools::Itertools;
+use sea_orm::ActiveValue::Set;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+
+use crate::{i18n, language, model, ranking::SortOrder, strings, DataStore, QUERY_RESULT_MAX};
+
+/// Prefix of the callback data of the buttons, followed by the setting and its value
+pub const CALLBACK_PREFIX: &str = "settings:";
+
+/// Numbers of inline query results sent at once that are offered
+const PAGE_SIZES: [usize; 3] = [10, 20, QUERY_RESULT_MAX];
+
+/// Languages whose tags users may prefer instead of the one of their Telegram client
+const LANGUAGES: [&str; 7] = ["en", "zh", "ja", "ko", "de", "es", "fr"];
+
+/// Value of the language setting that goes back to the language of the client
+const LANGUAGE_AUTO: &str = "auto";
+
+const LANGUAGE_BUTTONS_PER_ROW: usize = 4;
+
+/// Settings of a user, with the defaults of the bot in place of the ones they haven't picked
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    pub sort_order: SortOrder,
+    pub results_per_page: usize,
+    /// Language whose tags are preferred in the search results, instead of the one of the client
+    pub language: Option<String>,
+    /// Whether the stickers tagged [`crate::query::NSFW_TAG`] are hidden
+    pub safe_search: bool,
+    /// Whether the stickers chosen by the user before are ranked first and shown when browsing
+    pub personalized: bool,
+}
+
+impl Settings {
+    pub fn of(store: &DataStore, preference: Option<&model::user_preference::Model>) -> Self {
+        let sort_order = preference
+            .and_then(|preference| preference.sort_order.as_deref())
+            .and_then(SortOrder::parse)
+            .unwrap_or(store.default_sort_order);
+        let results_per_page = preference
+            .and_then(|preference| preference.results_per_page)
+            .map(|per_page| per_page.clamp(1, QUERY_RESULT_MAX as i32) as usize)
+            .unwrap_or(store.default_results_per_page);
+        let language = preference.and_then(|preference| preference.language.clone());
+        let safe_search = preference
+            .and_then(|preference| preference.safe_search)
+            .unwrap_or(store.default_safe_search);
+        // users may turn personal results off, but not on where the bot doesn't rank them
+        let personalized = store.personalized
+            && preference
+                .and_then(|preference| preference.personalized)
+                .unwrap_or(true);
+        Self {
+            sort_order,
+            results_per_page,
+            language,
+            safe_search,
+            personalized,
+        }
+    }
+}
+
+/// Change of a single setting, made by pressing one of the buttons
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    SortOrder(SortOrder),
+    ResultsPerPage(usize),
+    /// The language to prefer, or none to go back to the language of the client
+    Language(Option<String>),
+    SafeSearch(bool),
+    Personalized(bool),
+}
+
+impl Change {
+    /// Parses the callback data of a button, without the [`CALLBACK_PREFIX`]
+    pub fn parse(data: &str) -> Option<Self> {
+        let (setting, value) = data.split_once(':')?;
+        match setting {
+            "sort" => SortOrder::parse(value).map(Self::SortOrder),
+            "page" => match value.parse() {
+                Ok(per_page) if (1..=QUERY_RESULT_MAX).contains(&per_page) => {
+                    Some(Self::ResultsPerPage(per_page))
+                }
+                _ => None,
+            },
+            "lang" if value == LANGUAGE_AUTO => Some(Self::Language(None)),
+            "lang" if language::is_language_code(value) => {
+                Some(Self::Language(Some(value.to_owned())))
+            }
+            "safe" => parse_switch(value).map(Self::SafeSearch),
+            "personal" => parse_switch(value).map(Self::Personalized),
+            _ => None,
+        }
+    }
+
+    fn callback_data(&self) -> String {
+        let (setting, value) = match self {
+            Self::SortOrder(sort_order) => ("sort", sort_order.as_str().to_owned()),
+            Self::ResultsPerPage(per_page) => ("page", per_page.to_string()),
+            Self::Language(language) => (
+                "lang",
+                language.as_deref().unwrap_or(LANGUAGE_AUTO).to_owned(),
+            ),
+            Self::SafeSearch(on) => ("safe", switch_str(*on).to_owned()),
+            Self::Personalized(on) => ("personal", switch_str(*on).to_owned()),
+        };
+        format!("{CALLBACK_PREFIX}{setting}:{value}")
+    }
+
+    /// Preferences of the user with only the changed setting set, along with its column, which is
+    /// the only one to update if the user already has preferences
+    pub fn preference(
+        &self,
+        user_id: i64,
+    ) -> (
+        model::user_preference::ActiveModel,
+        model::user_preference::Column,
+    ) {
+        use model::user_preference::{ActiveModel, Column};
+
+        let preference = ActiveModel {
+            user_id: Set(user_id),
+            ..Default::default()
+        };
+        match self.clone() {
+            Self::SortOrder(sort_order) => (
+                ActiveModel {
+                    sort_order: Set(Some(sort_order.as_str().to_owned())),
+                    ..preference
+                },
+                Column::SortOrder,
+            ),
+            Self::ResultsPerPage(per_page) => (
+                ActiveModel {
+                    results_per_page: Set(Some(per_page as i32)),
+                    ..preference
+                },
+                Column::ResultsPerPage,
+            ),
+            Self::Language(language) => (
+                ActiveModel {
+                    language: Set(language),
+                    ..preference
+                },
+                Column::Language,
+            ),
+            Self::SafeSearch(on) => (
+                ActiveModel {
+                    safe_search: Set(Some(on)),
+                    ..preference
+                },
+                Column::SafeSearch,
+            ),
+            Self::Personalized(on) => (
+                ActiveModel {
+                    personalized: Set(Some(on)),
+                    ..preference
+                },
+                Column::Personalized,
+            ),
+        }
+    }
+}
+
+fn parse_switch(value: &str) -> Option<bool> {
+    match value {
+        "on" => Some(true),
+        "off" => Some(false),
+        _ => None,
+    }
+}
+
+fn switch_str(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+/// Buttons sent by /settings, with the current settings checked. The switches are checked while
+/// they're on, and turn them off when pressed.
+pub fn keyboard(
+    store: &DataStore,
+    settings: &Settings,
+    language: Option<&str>,
+) -> InlineKeyboardMarkup {
+    let button = |label: &str, checked: bool, change: Change| {
+        let label = i18n::translate(language, label);
+        let label = if checked {
+            format!("✓ {label}")
+        } else {
+            label.to_owned()
+        };
+        InlineKeyboardButton::callback(label, change.callback_data())
+    };
+
+    let sort_buttons = SortOrder::ALL
+        .into_iter()
+        .map(|sort_order| {
+            let label = match sort_order {
+                SortOrder::Relevance => strings::SORT_RELEVANCE,
+                SortOrder::Popularity => strings::SORT_POPULARITY,
+                SortOrder::Recency => strings::SORT_RECENCY,
+            };
+            button(
+                label,
+                sort_order == settings.sort_order,
+                Change::SortOrder(sort_order),
+            )
+        })
+        .collect_vec();
+
+    let per_page_label = i18n::translate(language, strings::RESULTS_PER_PAGE);
+    let page_buttons = PAGE_SIZES
+        .into_iter()
+        .map(|per_page| {
+            button(
+                &format!("{per_page} {per_page_label}"),
+                per_page == settings.results_per_page,
+                Change::ResultsPerPage(per_page),
+            )
+        })
+        .collect_vec();
+
+    let language_buttons = [None]
+        .into_iter()
+        .chain(LANGUAGES.map(Some))
+        .map(|code| {
+            button(
+                code.unwrap_or(strings::CLIENT_LANGUAGE),
+                code == settings.language.as_deref(),
+                Change::Language(code.map(str::to_owned)),
+            )
+        })
+        .chunks(LANGUAGE_BUTTONS_PER_ROW)
+        .into_iter()
+        .map(|row| row.collect_vec())
+        .collect_vec();
+
+    let mut switch_buttons = vec![button(
+        strings::SAFE_SEARCH,
+        settings.safe_search,
+        Change::SafeSearch(settings.safe_search == false),
+    )];
+    // there's nothing to turn on where the bot doesn't personalize the results
+    if store.personalized {
+        switch_buttons.push(button(
+            strings::PERSONAL_RESULTS,
+            settings.personalized,
+            Change::Personalized(settings.personalized == false),
+        ));
+    }
+
+    let mut buttons = vec![sort_buttons, page_buttons];
+    buttons.extend(language_buttons);
+    buttons.push(switch_buttons);
+    InlineKeyboardMarkup::new(buttons)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_round_trip_through_callback_data() {
+        let changes = [
+            Change::SortOrder(SortOrder::Recency),
+            Change::ResultsPerPage(20),
+            Change::Language(Some("ja".to_owned())),
+            Change::Language(None),
+            Change::SafeSearch(true),
+            Change::Personalized(false),
+        ];
+        for change in changes {
+            let data = change.callback_data();
+            let data = data.strip_prefix(CALLBACK_PREFIX).unwrap();
+            assert_eq!(Change::parse(data), Some(change));
+        }
+
+        assert_eq!(Change::parse("page:0"), None);
+        assert_eq!(Change::parse("page:51"), None);
+        assert_eq!(Change::parse("lang:english"), None);
+        assert_eq!(Change::parse("safe:maybe"), None);
+        assert_eq!(Change::parse("sort"), None);
+    }
+}
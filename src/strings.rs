@@ -1,8 +1,16 @@
+//! User-facing strings, in English
+//!
+//! The strings are also the message ids of the translations, see [`crate::i18n`]. Changing one of
+//! them requires updating the translations in `locales/` as well.
+
 pub const SENDER_UNKNOWN: &str = "Failed to find the sender of this message";
 pub const TAG_NOT_AUTHORIZED: &str = "You're not authorized to tag stickers";
 pub const TAGGED_STICKER: &str = "Tagged the sticker with the following tags:";
+pub const TAGS_ALREADY_PRESENT: &str = "The sticker already has the following tags:";
+pub const TAGGED_STICKER_SET: &str = "Tagged every sticker in the set with the following tags:";
 pub const USERNAME_MISSING: &str = "You must set a username (check your Telegram settings)";
 pub const NEED_APPROVAL: &str = "Great! Now tell the admin to approve your request";
+pub const ALREADY_REGISTERED: &str = "You have already registered";
 pub const NOT_REGISTERED: &str = "The specified user has not registered";
 pub const WRONG_ARGNUM: &str = "Wrong number of arguments";
 pub const NO_PERM: &str = "*You're not supposed to do that*";
@@ -10,5 +18,139 @@ pub const NO_STICKER_SET: &str =
     "Tagging is only supported for stickers that are contained in sticker sets";
 pub const STICKER_UNTAGGED: &str = "This sticker is not tagged";
 pub const UNTAG_SUCCESS: &str = "Successfully removed the specified tags";
+pub const NOTHING_TO_UNDO: &str = "There's nothing to undo";
+pub const ASK_FOR_TAGS: &str =
+    "Which tags should this sticker have? Send them separated by spaces, or /cancel";
+pub const SEND_STICKER_TO_TAG: &str = "Send me a sticker to tag it";
+pub const TAGGING_CANCELLED: &str = "Stopped tagging the sticker";
+pub const NOTHING_TO_CANCEL: &str = "There's nothing to cancel";
+pub const REMOVE_TAG: &str = "Remove:";
+pub const ADD_MORE_TAGS: &str = "Add more tags";
+pub const REMOVED_TAG: &str = "Removed the tag:";
+pub const TAG_ALREADY_REMOVED: &str = "This tag has already been removed";
+pub const NOT_YOUR_TAG: &str = "Only the tagger who added this tag can remove it here";
+pub const ADD_TAGS_IN_PRIVATE: &str = "Send me the tags in a private chat";
+pub const SUGGESTED_TAGS: &str = "Other stickers in the set have these tags, you might also want:";
+pub const APPLIED_TAG: &str = "Added the tag:";
+pub const TAG_ALREADY_APPLIED: &str = "The sticker already has this tag";
+pub const SUGGESTION_GONE: &str = "This suggestion is no longer available";
+pub const TAGS_PROPAGATED: &str = "Number of the other stickers in the set given the tags:";
+pub const ON_OFF_USAGE: &str = "Please choose one of: on, off";
+pub const PROPAGATION_ON: &str =
+    "From now on, your new tags are added to every sticker in the set, until /propagate off";
+pub const PROPAGATION_OFF: &str = "Your new tags are only added to the tagged sticker again";
+pub const NOTHING_PROPAGATED: &str = "You haven't propagated any of these tags to this set";
+pub const UNPROPAGATED: &str = "Number of propagated tags removed:";
+pub const PRIVATE_ON: &str =
+    "From now on, your new tags are private: only you find the stickers by them, until /private off";
+pub const PRIVATE_OFF: &str = "Your new tags are searchable by everyone again";
+pub const DUPLICATES_TAGGED: &str =
+    "Stickers looking like this one are already tagged in these sets, their tags are suggested below:";
+pub const NO_SIMILAR_STICKERS: &str = "No other sticker shares a tag with this one";
+pub const SIMILAR_STICKERS: &str = "Number of stickers sharing tags with this one:";
+pub const BROWSE_SIMILAR: &str = "Browse similar stickers";
+pub const FAVORITED: &str =
+    "Added the sticker to your favorites, which are listed first when browsing, or alone with fav:";
+pub const UNFAVORITED: &str = "Removed the sticker from your favorites";
+pub const FAVORITES_FULL: &str = "Your favorites are full, please remove some with /fav first";
+pub const TAGS_VOTED: &str =
+    "Counted your vote for the following tags, which someone else added already:";
+pub const VOTE_USAGE: &str = "Usage: /vote up <tag> or /vote down <tag>, replying to a sticker";
+pub const VOTE_NO_SUCH_TAG: &str = "The sticker doesn't have this tag";
+pub const VOTE_OWN_TAG: &str = "You added this tag yourself, which already counts as your vote";
+pub const VOTE_COUNTED: &str = "Counted your vote. Votes for the tag, minus the ones against it:";
+pub const VOTE_REMOVED_TAG: &str = "Counted your vote, which got the tag removed";
+pub const TOKEN_ISSUED: &str =
+    "Your token for the API, which replaces the previous one (keep it secret, and see the README for how to use it):";
+pub const TOKEN_PRIVATE_ONLY: &str = "Please ask for a token in a private chat with the bot";
+pub const UNDID_TAG: &str = "Removed the tags you just added:";
+pub const UNDID_UNTAG: &str = "Restored the tags you just removed:";
+pub const RESTORED_TAGS: &str = "Restored the tags you removed:";
+pub const NOTHING_TO_RESTORE: &str =
+    "You haven't removed any of these tags from this sticker lately";
+pub const NO_AUDIT_ENTRIES: &str = "The audit log is empty";
+pub const NO_REPLY_DOCUMENT: &str = "Please reply to a JSON file created by /export";
+pub const IMPORTED: &str = "Imported the following new entries:";
+pub const REFRESHED_SETS: &str = "Refreshed the following sticker sets:";
+pub const PURGED_DEAD: &str = "Deleted dead stickers:";
+pub const LEADERBOARD_EMPTY: &str = "Nobody has tagged any stickers yet";
+pub const LEADERBOARD_TAGS: &str = "Top taggers by number of tags:";
+pub const LEADERBOARD_CLICKS: &str = "Top taggers by clicks on their tagged stickers:";
+pub const GAPS_EMPTY: &str = "No searches have come up empty yet";
+pub const GAPS_HEADER: &str = "Most common searches without results, and how often they were made:";
 pub const NO_TAGS: &str = "Please supply at least one tag";
 pub const NO_REPLY_STICKER: &str = "Please reply to a sticker when using the /tag command";
+pub const TAGS_ON_STICKER: &str = "Tags on this sticker:";
+pub const TAGS_PENDING_REVIEW: &str =
+    "The new tags will show up in search results once a moderator has approved them";
+pub const PENDING_REVIEW: &str = "pending review";
+pub const STICKER_SET: &str = "Sticker set:";
+pub const STICKER_CLICKS: &str = "Times chosen:";
+pub const STICKER_INDEXED: &str = "Indexed on:";
+pub const HELP: &str = "To search for stickers, simply tag the bot and type your keywords. \
+                        Prefix a keyword with - to exclude the stickers tagged with it, \
+                        and use set:<name> to only search the sticker sets whose name contains <name>. \
+                        Wrap several words in double quotes to search for (or tag with) a phrase, \
+                        and use type:static, type:animated, type:video, type:emoji, type:gif, \
+                        type:photo or type:sticker to filter by the type of stickers.";
+pub const TAG_SUGGESTIONS: &str = "Tags:";
+pub const WELCOME: &str = "Hi! I find stickers by their tags. To search for some, type my \
+                           username followed by a few keywords in any chat, and pick one of the results.";
+pub const BECOME_TAGGER: &str = "The tags are added by taggers. To become one, send /register \
+                                 and wait for the admin to approve you. Then reply /tag <tags> \
+                                 to a sticker, or simply send it to me here.";
+pub const MORE_HELP: &str = "Send /help for the filters of searches and every command.";
+pub const TRY_EXAMPLES: &str = "Try searching for one of the most popular tags:";
+pub const SEARCH_IN_CHAT: &str = "Search in a chat";
+pub const SET_NAME_MISSING: &str =
+    "Please give the name or link of a sticker set, or reply to one of its stickers";
+pub const SET_NOT_INDEXED: &str = "No stickers from this set have been indexed yet";
+pub const SET_INDEXED_STICKERS: &str = "Indexed stickers from this set:";
+pub const BROWSE_SET: &str = "Browse";
+pub const PREFER_USAGE: &str =
+    "Please choose one of: static, animated, video, emoji, gif, photo, sticker, any";
+pub const PREFERENCE_SAVED: &str =
+    "Unless your query says otherwise, you'll only see stickers of type";
+pub const PREFERENCE_CLEARED: &str = "You'll see every type of sticker again";
+pub const SETTINGS: &str = "Settings of your searches, which only apply to you:";
+pub const SORT_RELEVANCE: &str = "Most relevant";
+pub const SORT_POPULARITY: &str = "Most popular";
+pub const SORT_RECENCY: &str = "Newest";
+pub const RESULTS_PER_PAGE: &str = "per page";
+pub const CLIENT_LANGUAGE: &str = "App language";
+pub const SAFE_SEARCH: &str = "Safe search";
+pub const PERSONAL_RESULTS: &str = "Personal results";
+pub const SETTINGS_SAVED: &str = "Saved your settings";
+pub const RATE_LIMITED: &str = "You're going too fast, please wait a moment";
+pub const INTERNAL_ERROR: &str = "Something went wrong, please try again later";
+pub const NEW_REGISTRATION: &str = "New tagger registration:";
+pub const REGISTRATION_APPROVED: &str = "Approved tagger";
+pub const REGISTRATION_DENIED: &str = "Denied tagger";
+pub const REPORT_FILED: &str = "Thanks, the moderators will have a look at the tags";
+pub const REPORTS_DISABLED: &str = "Reports are not accepted at the moment";
+pub const NEW_REPORT: &str = "New report on the tags of this sticker by";
+pub const REPORT_DISMISSED: &str = "Report dismissed by";
+pub const REPORT_ALREADY_RESOLVED: &str = "The report has already been dismissed";
+pub const REPORT_TAG_DELETED: &str = "Deleted the tag";
+pub const REVIEW_QUEUE_EMPTY: &str = "No tags are awaiting review";
+pub const REVIEW_QUEUE_SIZE: &str = "Tags awaiting review:";
+pub const REVIEW_PENDING_TAGS: &str = "Tags of this sticker awaiting review:";
+pub const REVIEW_DONE: &str = "Reviewed every tag of this sticker";
+pub const REVIEW_ALREADY_DONE: &str = "The tag has already been reviewed";
+pub const REVIEW_TAG_APPROVED: &str = "Approved the tag";
+pub const REVIEW_TAG_REJECTED: &str = "Rejected the tag";
+pub const ADMIN_CHAT_ONLY: &str = "This command can only be used in the admin chat";
+pub const CLEAR_TAGS_CONFIRM: &str = "Delete every tag of this sticker?";
+pub const CLEARED_TAGS: &str = "Deleted every tag of this sticker:";
+pub const CLEAR_TAGS_CANCELLED: &str = "Kept the tags of this sticker";
+pub const RETAG_USAGE: &str = "Usage: /retag <old tag> <new tag> [set:<set name>] [--dry-run]";
+pub const RETAGGED: &str = "Number of stickers whose tag was renamed:";
+pub const RETAG_MERGED: &str =
+    "Number of stickers that already had the new tag, whose old tag was removed:";
+pub const RETAG_DRY_RUN: &str = "Nothing was changed, since this was a dry run";
+pub const MERGE_NOT_INDEXED: &str = "This sticker has not been indexed";
+pub const MERGE_PICK_CANONICAL: &str =
+    "Now reply /merge to the sticker to keep, which this duplicate will be merged into";
+pub const MERGE_NO_DUPLICATE: &str = "No other indexed sticker has this id";
+pub const MERGED_STICKERS: &str = "Merged the duplicate into this sticker, moving over its tags:";
+pub const VIDEO_RESULT_TITLE: &str = "Video";
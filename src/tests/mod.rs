@@ -0,0 +1,126 @@
+//! Tests of the handlers and the data store against an in-memory sqlite database, and a mock of
+//! the Telegram Bot API where they talk to Telegram
+//!
+//! The pure logic of parsing queries and tags, and of ranking, is tested next to it, e.g. in
+//! [`crate::query`]. The migrations are tested against the other databases in [`migration`].
+
+mod migration;
+mod store;
+mod telegram;
+
+use std::sync::Arc;
+
+use ::config::{File, FileFormat};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectOptions, Database, EntityTrait, QueryFilter, QueryOrder,
+    Set,
+};
+use sea_orm_migration::MigratorTrait;
+
+use crate::{auth, config::Config, language, migration, model, query, DataStore};
+
+/// Configuration of the tests, which is the default one but for the required settings
+fn test_config(extra: &str) -> Config {
+    let toml = format!("teloxide_token = \"123:test\"\nstickers_secret = \"secret\"\n{extra}");
+    ::config::Config::builder()
+        .add_source(File::from_str(&toml, FileFormat::Toml))
+        .build()
+        .and_then(|config| config.try_deserialize())
+        .expect("test config to be valid")
+}
+
+/// Data store on a fresh in-memory database, with the settings in `extra` on top of the defaults
+async fn test_store_with(extra: &str) -> Arc<DataStore> {
+    // every connection to an in-memory database gets a database of its own
+    let mut options = ConnectOptions::new("sqlite::memory:");
+    options.max_connections(1).sqlx_logging(false);
+    let db = Database::connect(options).await.expect("database to open");
+    migration::Migrator::up(&db, None)
+        .await
+        .expect("migrations to apply");
+    Arc::new(DataStore::new(db.clone(), db, &test_config(extra)))
+}
+
+async fn test_store() -> Arc<DataStore> {
+    test_store_with("").await
+}
+
+/// Registers an allowed tagger
+async fn insert_tagger(store: &DataStore, user_id: i64, username: &str) -> auth::AuthorizedTagger {
+    let user = model::user::ActiveModel {
+        user_id: Set(user_id),
+        username: Set(username.to_owned()),
+        allowed: Set(true),
+        propagate_tags: Set(false),
+        private_tags: Set(false),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .expect("tagger to be inserted");
+    auth::AuthorizedTagger { user }
+}
+
+/// Indexes a static sticker of the set
+async fn insert_sticker(
+    store: &DataStore,
+    file_unique_id: &str,
+    set_name: &str,
+    popularity: i64,
+) -> model::sticker::Model {
+    model::sticker::ActiveModel {
+        file_unique_id: Set(file_unique_id.to_owned()),
+        file_id: Set(format!("file-{file_unique_id}")),
+        set_name: Set(set_name.to_owned()),
+        popularity: Set(popularity),
+        dead: Set(false),
+        indexed_at: Set(Some(Utc::now())),
+        is_animated: Set(false),
+        is_video: Set(false),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .expect("sticker to be inserted")
+}
+
+/// Tags the sticker with approved tags, given the way they are to /tag
+async fn insert_tags(
+    store: &DataStore,
+    tagger: &auth::AuthorizedTagger,
+    sticker: &model::sticker::Model,
+    text: &str,
+) {
+    for (tag, tag_language) in language::parse_tags(text) {
+        model::tagged_sticker::ActiveModel {
+            namespace: Set(query::tag_namespace(&tag)),
+            romanized: Set(language::romanize(&tag, tag_language.as_deref())),
+            tag: Set(tag),
+            sticker_id: Set(sticker.id),
+            tagger_id: Set(tagger.user.id),
+            ts: Set(Utc::now()),
+            approved: Set(true),
+            language: Set(tag_language),
+            ..Default::default()
+        }
+        .insert(&store.db)
+        .await
+        .expect("tag to be inserted");
+    }
+    store.invalidate_query_cache();
+}
+
+/// Tags of the sticker, in the order they were added
+async fn tags_of(store: &DataStore, sticker: &model::sticker::Model) -> Vec<String> {
+    model::tagged_sticker::Entity::find()
+        .filter(model::tagged_sticker::Column::StickerId.eq(sticker.id))
+        .filter(model::tagged_sticker::Column::DeletedAt.is_null())
+        .order_by_asc(model::tagged_sticker::Column::Id)
+        .all(&store.db)
+        .await
+        .expect("tags to be found")
+        .into_iter()
+        .map(|tagged| tagged.tag)
+        .collect()
+}
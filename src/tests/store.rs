@@ -0,0 +1,649 @@
+//! Searches and the bookkeeping of the data store
+
+use std::cell::Cell;
+
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnAcquireErr, DbErr, EntityTrait, IntoActiveModel,
+    QueryFilter, QueryOrder, Set,
+};
+
+use super::{insert_sticker, insert_tagger, insert_tags, test_store, test_store_with};
+use crate::{
+    auth, claim_scheduled_job, find_stickers, limit_to_page, model, query, reputation,
+    resolve_result_id, result_id, seed_admin, suggest_tags_for_sticker, BotError, DataStore,
+    UserError, MATCH_WINDOW, POPULARITY_RECOMPUTE_JOB,
+};
+
+/// Telegram user id of the user searching
+const USER_ID: i64 = 42;
+
+/// Unique file ids of the stickers found for the inline query, in order
+async fn search(store: &DataStore, text: &str, language: Option<&str>) -> Vec<String> {
+    search_unique_ids(store, &query::SearchQuery::parse(text), language).await
+}
+
+/// Unique file ids of the stickers found for the parsed query, in order
+async fn search_unique_ids(
+    store: &DataStore,
+    search_query: &query::SearchQuery,
+    language: Option<&str>,
+) -> Vec<String> {
+    find_stickers(store, search_query, USER_ID, language)
+        .await
+        .expect("search to succeed")
+        .into_iter()
+        .map(|sticker| sticker.file_unique_id)
+        .collect()
+}
+
+#[tokio::test]
+async fn search_matches_every_term_by_default() {
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, 1, "tagger").await;
+    let happy_cat = insert_sticker(&store, "happy_cat", "cats", 0).await;
+    let cat = insert_sticker(&store, "cat", "cats", 0).await;
+    insert_tags(&store, &tagger, &happy_cat, "cat happy").await;
+    insert_tags(&store, &tagger, &cat, "cat").await;
+
+    assert_eq!(search(&store, "cat happy", None).await, ["happy_cat"]);
+    assert_eq!(search(&store, "cat", None).await, ["happy_cat", "cat"]);
+    assert_eq!(search(&store, "cat -happy", None).await, ["cat"]);
+    assert!(search(&store, "dog", None).await.is_empty());
+}
+
+#[tokio::test]
+async fn search_in_any_mode_ranks_by_matching_terms() {
+    let store = test_store_with("query_match_mode = \"any\"").await;
+    let tagger = insert_tagger(&store, 1, "tagger").await;
+    let happy_cat = insert_sticker(&store, "happy_cat", "cats", 0).await;
+    let cat = insert_sticker(&store, "cat", "cats", 0).await;
+    let dog = insert_sticker(&store, "dog", "dogs", 100).await;
+    insert_tags(&store, &tagger, &happy_cat, "cat happy").await;
+    insert_tags(&store, &tagger, &cat, "cat").await;
+    insert_tags(&store, &tagger, &dog, "dog").await;
+
+    assert_eq!(
+        search(&store, "cat happy", None).await,
+        ["happy_cat", "cat"]
+    );
+    // matching as many terms, the more popular one comes first
+    assert_eq!(
+        search(&store, "happy dog", None).await,
+        ["dog", "happy_cat"]
+    );
+}
+
+#[tokio::test]
+async fn search_ranks_the_best_matches_beyond_the_window() {
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, 1, "tagger").await;
+    for i in 0..MATCH_WINDOW + 10 {
+        let sticker = insert_sticker(&store, &format!("cat{i}"), "cats", 1000 - i as i64).await;
+        insert_tags(&store, &tagger, &sticker, "cat").await;
+    }
+    // the least popular of the stickers is picked after the window, but voted for the most
+    let voted = insert_sticker(&store, "voted", "cats", 0).await;
+    insert_tags(&store, &tagger, &voted, "cat").await;
+    let tag = model::tagged_sticker::Entity::find()
+        .filter(model::tagged_sticker::Column::StickerId.eq(voted.id))
+        .one(&store.db)
+        .await
+        .unwrap()
+        .expect("tag to be inserted");
+    model::tag_vote::Entity::insert_many((0..30).map(|user_id| model::tag_vote::ActiveModel {
+        tagged_sticker_id: Set(tag.id),
+        user_id: Set(user_id),
+        value: Set(model::tag_vote::UP),
+        ts: Set(Utc::now()),
+    }))
+    .exec(&store.db)
+    .await
+    .unwrap();
+
+    let mut search_query = query::SearchQuery::parse("cat");
+    limit_to_page(&mut search_query, 0, 10);
+    let first_page = search_unique_ids(&store, &search_query, None).await;
+    assert_eq!(first_page[..3], ["voted", "cat0", "cat1"]);
+    // the next page goes on where the first one stopped
+    limit_to_page(&mut search_query, 10, 10);
+    let second_page = search_unique_ids(&store, &search_query, None).await;
+    assert_eq!(second_page[9..11], ["cat8", "cat9"]);
+}
+
+#[tokio::test]
+async fn exact_matches_rank_above_popular_partial_ones() {
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, 1, "tagger").await;
+    let category = insert_sticker(&store, "category", "a", 1000).await;
+    let cat = insert_sticker(&store, "cat", "b", 0).await;
+    insert_tags(&store, &tagger, &category, "category").await;
+    insert_tags(&store, &tagger, &cat, "cat").await;
+
+    assert_eq!(search(&store, "cat", None).await, ["cat", "category"]);
+}
+
+#[tokio::test]
+async fn cjk_terms_match_stickers_whose_tags_cover_them() {
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, 1, "tagger").await;
+    let crying_cat = insert_sticker(&store, "crying_cat", "cats", 0).await;
+    let crying = insert_sticker(&store, "crying", "cats", 0).await;
+    let phrase = insert_sticker(&store, "phrase", "cats", 0).await;
+    insert_tags(&store, &tagger, &crying_cat, "貓咪 哭哭").await;
+    insert_tags(&store, &tagger, &crying, "哭哭").await;
+    insert_tags(&store, &tagger, &phrase, "貓咪哭哭").await;
+
+    assert_eq!(
+        search(&store, "貓咪哭哭", None).await,
+        ["crying_cat", "phrase"]
+    );
+    assert_eq!(
+        search(&store, "哭哭", None).await,
+        ["crying_cat", "crying", "phrase"]
+    );
+}
+
+#[tokio::test]
+async fn search_prefers_tags_in_the_language_of_the_user() {
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, 1, "tagger").await;
+    let english = insert_sticker(&store, "english", "a", 0).await;
+    let unknown = insert_sticker(&store, "unknown", "b", 10).await;
+    insert_tags(&store, &tagger, &english, "en:cry").await;
+    insert_tags(&store, &tagger, &unknown, "cry").await;
+
+    assert_eq!(
+        search(&store, "cry", Some("en")).await,
+        ["english", "unknown"]
+    );
+    assert_eq!(
+        search(&store, "cry", Some("ja")).await,
+        ["unknown", "english"]
+    );
+    assert_eq!(search(&store, "cry", None).await, ["unknown", "english"]);
+}
+
+#[tokio::test]
+async fn namespaces_filter_and_list_stickers() {
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, 1, "tagger").await;
+    let character = insert_sticker(&store, "character", "a", 0).await;
+    let plain = insert_sticker(&store, "plain", "a", 0).await;
+    insert_tags(&store, &tagger, &character, "char:miku").await;
+    insert_tags(&store, &tagger, &plain, "miku").await;
+
+    assert_eq!(search(&store, "miku", None).await, ["character", "plain"]);
+    assert_eq!(search(&store, "char: miku", None).await, ["character"]);
+    assert_eq!(search(&store, "char:", None).await, ["character"]);
+}
+
+#[tokio::test]
+async fn custom_emoji_are_searched_like_stickers() {
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, 1, "tagger").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    let emoji = insert_sticker(&store, "emoji", "cat_emoji", 0).await;
+    let mut active_emoji = emoji.clone().into_active_model();
+    active_emoji.kind = Set(model::sticker::CUSTOM_EMOJI.to_owned());
+    active_emoji.update(&store.db).await.unwrap();
+    insert_tags(&store, &tagger, &sticker, "cat").await;
+    insert_tags(&store, &tagger, &emoji, "cat").await;
+
+    assert_eq!(search(&store, "cat", None).await, ["sticker", "emoji"]);
+    assert_eq!(search(&store, "cat type:emoji", None).await, ["emoji"]);
+}
+
+#[tokio::test]
+async fn tags_scoped_to_groups_are_only_searchable_by_their_members() {
+    let store = test_store_with("group_scoped_tags = true").await;
+    let tagger = insert_tagger(&store, 1, "tagger").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    model::tagged_sticker::ActiveModel {
+        tag: Set("inside joke".to_owned()),
+        sticker_id: Set(sticker.id),
+        tagger_id: Set(tagger.user.id),
+        ts: Set(Utc::now()),
+        approved: Set(true),
+        chat_id: Set(Some(-100)),
+        scope: Set(model::tagged_sticker::scope(
+            Some(-100),
+            model::tagged_sticker::PUBLIC,
+            tagger.user.id,
+        )),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .expect("tag to be inserted");
+
+    assert!(search(&store, "joke", None).await.is_empty());
+    let mut search_query = query::SearchQuery::parse("joke");
+    search_query.chats = vec![-100];
+    let stickers = find_stickers(&store, &search_query, USER_ID, None)
+        .await
+        .unwrap();
+    assert_eq!(stickers.len(), 1);
+    assert_eq!(stickers[0].id, sticker.id);
+}
+
+#[tokio::test]
+async fn nsfw_stickers_are_hidden_by_safe_search_unless_asked_for() {
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, USER_ID, "tagger").await;
+    let safe = insert_sticker(&store, "safe", "cats", 0).await;
+    insert_tags(&store, &tagger, &safe, "cat").await;
+    let nsfw = insert_sticker(&store, "nsfw", "cats", 0).await;
+    insert_tags(&store, &tagger, &nsfw, "cat nsfw").await;
+
+    let safe_search = |text: &str| {
+        let mut search_query = query::SearchQuery::parse(text);
+        search_query.apply_safe_search(true);
+        search_query
+    };
+    let stickers = find_stickers(&store, &safe_search("cat"), USER_ID, None)
+        .await
+        .unwrap();
+    assert_eq!(stickers.len(), 1);
+    assert_eq!(stickers[0].id, safe.id);
+    let stickers = find_stickers(&store, &safe_search("nsfw: cat"), USER_ID, None)
+        .await
+        .unwrap();
+    assert_eq!(stickers.len(), 2);
+}
+
+#[tokio::test]
+async fn reputation_trusts_taggers_and_ranks_their_stickers() {
+    let store = test_store().await;
+    let careless = insert_tagger(&store, 1, "careless").await;
+    let careful = insert_tagger(&store, 2, "careful").await;
+    let careless_cat = insert_sticker(&store, "careless_cat", "cats", 0).await;
+    let careful_cat = insert_sticker(&store, "careful_cat", "cats", 0).await;
+    let dog = insert_sticker(&store, "dog", "dogs", 0).await;
+    insert_tags(&store, &careless, &careless_cat, "cat").await;
+    insert_tags(&store, &careful, &careful_cat, "cat").await;
+    insert_tags(&store, &careless, &dog, "cat").await;
+
+    // a moderator removes the wrong tag of the careless tagger
+    let wrong_tag = model::tagged_sticker::Entity::find()
+        .filter(model::tagged_sticker::Column::StickerId.eq(dog.id))
+        .one(&store.db)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut wrong_tag = wrong_tag.into_active_model();
+    wrong_tag.deleted_at = Set(Some(Utc::now()));
+    wrong_tag.deleted_by = Set(Some(99));
+    wrong_tag.update(&store.db).await.unwrap();
+    store.invalidate_query_cache();
+
+    assert!(careful.is_trusted(&store.db, 1).await.unwrap());
+    assert!(careless.is_trusted(&store.db, 1).await.unwrap() == false);
+
+    reputation::recompute(&store.db).await.unwrap();
+    assert_eq!(
+        search(&store, "cat", None).await,
+        ["careful_cat", "careless_cat"]
+    );
+}
+
+#[tokio::test]
+async fn private_tags_are_only_searchable_by_their_tagger() {
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, USER_ID, "tagger").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    model::tagged_sticker::ActiveModel {
+        tag: Set("mnemonic".to_owned()),
+        sticker_id: Set(sticker.id),
+        tagger_id: Set(tagger.user.id),
+        ts: Set(Utc::now()),
+        approved: Set(true),
+        visibility: Set(model::tagged_sticker::PRIVATE.to_owned()),
+        scope: Set(model::tagged_sticker::scope(
+            None,
+            model::tagged_sticker::PRIVATE,
+            tagger.user.id,
+        )),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .expect("tag to be inserted");
+
+    assert!(search(&store, "mnemonic", None).await.is_empty());
+    let mut search_query = query::SearchQuery::parse("mnemonic");
+    search_query.tagger_id = Some(tagger.user.id);
+    let stickers = find_stickers(&store, &search_query, USER_ID, None)
+        .await
+        .unwrap();
+    assert_eq!(stickers.len(), 1);
+    assert_eq!(stickers[0].id, sticker.id);
+}
+
+#[tokio::test]
+async fn favorites_are_listed_most_recently_added_first() {
+    let store = test_store().await;
+    let older = insert_sticker(&store, "older", "a", 0).await;
+    let newer = insert_sticker(&store, "newer", "a", 0).await;
+    insert_sticker(&store, "other", "a", 0).await;
+    for (sticker, ts) in [
+        (&older, Utc::now() - Duration::hours(1)),
+        (&newer, Utc::now()),
+    ] {
+        model::favorite::ActiveModel {
+            user_id: Set(USER_ID),
+            sticker_id: Set(sticker.id),
+            ts: Set(ts),
+        }
+        .insert(&store.db)
+        .await
+        .expect("favorite to be inserted");
+    }
+
+    assert_eq!(search(&store, "fav:", None).await, ["newer", "older"]);
+}
+
+#[tokio::test]
+async fn stickers_looking_the_same_are_shown_once() {
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, 1, "tagger").await;
+    let original = insert_sticker(&store, "original", "a", 1).await;
+    let reupload = insert_sticker(&store, "reupload", "b", 5).await;
+    let other = insert_sticker(&store, "other", "a", 0).await;
+    for (sticker, hash) in [(&original, 42), (&reupload, 42), (&other, 7)] {
+        insert_tags(&store, &tagger, sticker, "cat").await;
+        model::sticker_hash::new_active_model(sticker.id, Some(hash), Utc::now())
+            .insert(&store.db)
+            .await
+            .expect("hash to be inserted");
+    }
+
+    assert_eq!(search(&store, "cat", None).await, ["reupload", "other"]);
+}
+
+#[tokio::test]
+async fn matches_are_cached_until_tags_change() {
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, 1, "tagger").await;
+    let cat = insert_sticker(&store, "cat", "a", 0).await;
+    assert!(search(&store, "cat", None).await.is_empty());
+
+    // tagged behind the back of the data store, which still has the old matches
+    model::tagged_sticker::ActiveModel {
+        tag: Set("cat".to_owned()),
+        sticker_id: Set(cat.id),
+        tagger_id: Set(tagger.user.id),
+        ts: Set(Utc::now()),
+        approved: Set(true),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .expect("tag to be inserted");
+    assert!(search(&store, "cat", None).await.is_empty());
+
+    store.invalidate_query_cache();
+    assert_eq!(search(&store, "cat", None).await, ["cat"]);
+}
+
+#[tokio::test]
+async fn chosen_stickers_gain_popularity_when_flushed() {
+    let store = test_store().await;
+    let sticker = insert_sticker(&store, "sticker", "a", 3).await;
+    store.record_chosen(sticker.id, Utc::now());
+    store.record_chosen(sticker.id, Utc::now());
+    store.flush_popularity().await.expect("flush to succeed");
+
+    let sticker = model::sticker::Entity::find_by_id(sticker.id)
+        .one(&store.db)
+        .await
+        .expect("sticker to be found")
+        .expect("sticker to exist");
+    assert_eq!(sticker.popularity, 5);
+    assert!(sticker.last_used.is_some());
+}
+
+/// Logs a choice of the sticker at `ts`, and counts it towards its popularity
+async fn choose(store: &DataStore, sticker_id: i32, ts: DateTime<Utc>) {
+    model::chosen_result::ActiveModel {
+        sticker_id: Set(sticker_id),
+        user_id: Set(USER_ID),
+        query: Set("cat".to_owned()),
+        ts: Set(ts),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .expect("choice to be logged");
+    store.record_chosen(sticker_id, ts);
+}
+
+async fn popularity_of(store: &DataStore, sticker_id: i32) -> i64 {
+    model::sticker::Entity::find_by_id(sticker_id)
+        .one(&store.db)
+        .await
+        .expect("sticker to be found")
+        .expect("sticker to exist")
+        .popularity
+}
+
+#[tokio::test]
+async fn choices_counted_by_a_recomputation_are_not_flushed_again() {
+    let store = test_store().await;
+    let sticker = insert_sticker(&store, "sticker", "a", 0).await;
+    choose(&store, sticker.id, Utc::now() - Duration::seconds(1)).await;
+    choose(&store, sticker.id, Utc::now() - Duration::seconds(1)).await;
+
+    claim_scheduled_job(
+        &store.db,
+        POPULARITY_RECOMPUTE_JOB,
+        std::time::Duration::from_secs(60),
+    )
+    .await
+    .expect("job to be claimed");
+    store
+        .recompute_popularity()
+        .await
+        .expect("recomputation to succeed");
+    assert_eq!(popularity_of(&store, sticker.id).await, 2);
+
+    // only the choice made since is added by the flush
+    choose(&store, sticker.id, Utc::now() + Duration::seconds(1)).await;
+    store.flush_popularity().await.expect("flush to succeed");
+    assert_eq!(popularity_of(&store, sticker.id).await, 3);
+}
+
+#[tokio::test]
+async fn users_are_throttled_after_a_burst() {
+    let store = test_store_with("rate_limit_per_minute = 1\nrate_limit_burst = 2").await;
+    assert!(store.throttle(USER_ID).is_ok());
+    assert!(store.throttle(USER_ID).is_ok());
+    assert!(matches!(
+        store.throttle(USER_ID),
+        Err(UserError::RateLimited)
+    ));
+    // every user has a limit of their own
+    assert!(store.throttle(USER_ID + 1).is_ok());
+}
+
+#[tokio::test]
+async fn transient_database_errors_are_retried() {
+    let store = test_store_with("db_retries = 2").await;
+    let attempts = Cell::new(0);
+    let fail_with = |e: fn() -> DbErr, failures: u32| {
+        attempts.set(0);
+        store.retry(move || {
+            attempts.set(attempts.get() + 1);
+            let res = if attempts.get() > failures {
+                Ok(())
+            } else {
+                Err(BotError::from(e()))
+            };
+            async move { res }
+        })
+    };
+    let timeout = || DbErr::ConnectionAcquire(ConnAcquireErr::Timeout);
+
+    assert!(fail_with(timeout, 2).await.is_ok());
+    assert_eq!(attempts.get(), 3);
+    assert!(matches!(
+        fail_with(timeout, 3).await,
+        Err(BotError::Database(_))
+    ));
+    assert_eq!(attempts.get(), 3);
+    // other errors aren't going to go away by themselves
+    let not_found = || DbErr::RecordNotFound("sticker".to_owned());
+    assert!(fail_with(not_found, 1).await.is_err());
+    assert_eq!(attempts.get(), 1);
+}
+
+#[tokio::test]
+async fn result_ids_resolve_to_stickers() {
+    let store = test_store().await;
+    let sticker = insert_sticker(&store, "AgADBAADq-_", "a", 0).await;
+
+    let id = result_id(&sticker.file_unique_id);
+    assert!(id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_".contains(c)));
+    assert_eq!(resolve_result_id(&store.db, &id).await.unwrap(), sticker.id);
+    // results sent before the ids were encoded carry the row id
+    let row_id = sticker.id.to_string();
+    assert_eq!(
+        resolve_result_id(&store.db, &row_id).await.unwrap(),
+        sticker.id
+    );
+    assert!(matches!(
+        resolve_result_id(&store.db, "not base64!").await,
+        Err(BotError::ChosenParse(_))
+    ));
+    assert!(matches!(
+        resolve_result_id(&store.db, &result_id("unknown")).await,
+        Err(BotError::NoSuchSticker)
+    ));
+}
+
+#[tokio::test]
+async fn the_admin_is_registered_as_an_allowed_tagger() {
+    let store = test_store().await;
+    // a registration awaiting approval is approved instead
+    model::user::ActiveModel {
+        user_id: Set(USER_ID),
+        username: Set("someone".to_owned()),
+        allowed: Set(false),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .unwrap();
+
+    seed_admin(&store.db, USER_ID).await.unwrap();
+    // the admin is seeded on every start
+    seed_admin(&store.db, USER_ID + 1).await.unwrap();
+    seed_admin(&store.db, USER_ID + 1).await.unwrap();
+
+    let users = model::user::Entity::find()
+        .order_by_asc(model::user::Column::Id)
+        .all(&store.db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|user| (user.user_id, user.username, user.allowed))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        users,
+        [
+            (USER_ID, "someone".to_owned(), true),
+            (USER_ID + 1, String::new(), true),
+        ]
+    );
+
+    // the username of an admin is filled in once they talk to the bot
+    let admin: teloxide::types::User = serde_json::from_value(serde_json::json!({
+        "id": USER_ID + 1,
+        "is_bot": false,
+        "first_name": "Admin",
+        "username": "boss",
+    }))
+    .unwrap();
+    let tagger = auth::authorize_tagger_user(&store.db, &admin)
+        .await
+        .unwrap();
+    assert_eq!(tagger.user.username, "boss");
+    let admin = model::user::Entity::find()
+        .filter(model::user::Column::UserId.eq(USER_ID + 1))
+        .one(&store.db)
+        .await
+        .unwrap()
+        .expect("admin to be registered");
+    assert_eq!(admin.username, "boss");
+}
+
+#[tokio::test]
+async fn loose_media_get_no_suggestions_from_each_other() {
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, 1, "tagger").await;
+    let cat = insert_sticker(&store, "cat", "cats", 0).await;
+    let kitten = insert_sticker(&store, "kitten", "cats", 0).await;
+    let gif = insert_sticker(&store, "gif", "", 0).await;
+    let photo = insert_sticker(&store, "photo", "", 0).await;
+    insert_tags(&store, &tagger, &cat, "cat").await;
+    insert_tags(&store, &tagger, &gif, "dance").await;
+
+    let suggestions = suggest_tags_for_sticker(&store.db, kitten.id, &[], None)
+        .await
+        .unwrap();
+    assert_eq!(
+        suggestions
+            .into_iter()
+            .map(|(tag, _)| tag)
+            .collect::<Vec<_>>(),
+        ["cat"]
+    );
+    assert!(suggest_tags_for_sticker(&store.db, photo.id, &[], None)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn tags_scoped_to_a_group_are_only_suggested_there() {
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, 1, "tagger").await;
+    let cat = insert_sticker(&store, "cat", "cats", 0).await;
+    let kitten = insert_sticker(&store, "kitten", "cats", 0).await;
+    insert_tags(&store, &tagger, &cat, "cat").await;
+    model::tagged_sticker::ActiveModel {
+        tag: Set("inside_joke".to_owned()),
+        sticker_id: Set(cat.id),
+        tagger_id: Set(tagger.user.id),
+        ts: Set(Utc::now()),
+        approved: Set(true),
+        chat_id: Set(Some(-5)),
+        scope: Set(model::tagged_sticker::scope(
+            Some(-5),
+            model::tagged_sticker::PUBLIC,
+            tagger.user.id,
+        )),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .unwrap();
+
+    let kitten_id = kitten.id;
+    let suggested = |chat_id| {
+        let store = &store;
+        async move {
+            suggest_tags_for_sticker(&store.db, kitten_id, &[], chat_id)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|(tag, _)| tag)
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(suggested(None).await, ["cat"]);
+    assert_eq!(suggested(Some(-6)).await, ["cat"]);
+    assert_eq!(suggested(Some(-5)).await, ["cat", "inside_joke"]);
+}
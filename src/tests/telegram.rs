@@ -0,0 +1,1481 @@
+//! Handlers run against a mock of the Telegram Bot API, which records the requests they make
+
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder, Set,
+};
+use serde_json::{json, Value};
+use teloxide::{
+    adaptors::throttle::Limits,
+    requests::RequesterExt,
+    types::{CallbackQuery, ChatId, ChosenInlineResult, InlineQuery, Message, Update, UserId},
+    update_listeners::AsUpdateStream,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+use tokio_stream::StreamExt;
+use wiremock::{
+    matchers::{any, body_partial_json, method, path_regex},
+    Mock, MockServer, ResponseTemplate,
+};
+
+use super::{insert_sticker, insert_tagger, insert_tags, tags_of, test_store, test_store_with};
+use crate::{
+    backup, callback_query_handler, chosen_inline_result_handler, command_handler, commands,
+    find_tagged_duplicates, handle_allow_command, handle_audit_command, handle_chown_command,
+    handle_deny_command, handle_list_tags_command, handle_merge_command, handle_restore_command,
+    handle_retag_command, handle_review_callback, handle_tag_command, handle_untag_command,
+    handle_vote_command, inline_query_handler, model, query, result_id, searchable_chats, strings,
+    tag_sticker, vote_balances, webhook, Bot, DataStore, TagTarget,
+};
+
+/// Telegram user id of the tagger, who talks to the bot in a private chat
+const TAGGER_ID: i64 = 7;
+
+/// Mock of the Bot API answering the methods used by the handlers, and failing any other
+async fn telegram_server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path_regex("/sendMessage$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ok": true,
+            "result": message_json(json!({ "text": "reply" })),
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path_regex("/answerInlineQuery$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ok": true,
+            "result": true,
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "ok": false,
+            "error_code": 400,
+            "description": "Bad Request: not mocked",
+        })))
+        .with_priority(u8::MAX)
+        .mount(&server)
+        .await;
+    server
+}
+
+fn test_bot(server: &MockServer) -> Bot {
+    let api_url = url::Url::parse(&server.uri()).expect("mock server to have a valid url");
+    teloxide::Bot::new("123:test")
+        .set_api_url(api_url)
+        .throttle(Limits::default())
+}
+
+/// Bodies of the requests made for the Bot API method
+async fn sent_requests(server: &MockServer, api_method: &str) -> Vec<Value> {
+    server
+        .received_requests()
+        .await
+        .expect("requests to be recorded")
+        .into_iter()
+        .filter(|request| request.url.path().ends_with(&format!("/{api_method}")))
+        .map(|request| serde_json::from_slice(&request.body).expect("request body to be json"))
+        .collect()
+}
+
+fn user_json() -> Value {
+    json!({
+        "id": TAGGER_ID,
+        "is_bot": false,
+        "first_name": "Tagger",
+        "username": "tagger",
+        "language_code": "en",
+    })
+}
+
+fn sticker_json(file_unique_id: &str) -> Value {
+    json!({
+        "file_id": format!("file-{file_unique_id}"),
+        "file_unique_id": file_unique_id,
+        "type": "regular",
+        "width": 512,
+        "height": 512,
+        "is_animated": false,
+        "is_video": false,
+        "emoji": "🐱",
+        "set_name": "cats",
+    })
+}
+
+/// Message sent by the tagger in the private chat, with the fields in `content` on top
+fn message_json(content: Value) -> Value {
+    let mut message = json!({
+        "message_id": 1,
+        "date": Utc::now().timestamp(),
+        "chat": { "id": TAGGER_ID, "type": "private", "first_name": "Tagger" },
+        "from": user_json(),
+    });
+    message
+        .as_object_mut()
+        .unwrap()
+        .extend(content.as_object().unwrap().clone());
+    message
+}
+
+fn message(content: Value) -> Message {
+    serde_json::from_value(message_json(content)).expect("message to be valid")
+}
+
+/// Stores that the sticker has no image to hash, so that looking for duplicates of it doesn't
+/// download it
+async fn insert_unhashable(store: &DataStore, sticker: &model::sticker::Model) {
+    model::sticker_hash::new_active_model(sticker.id, None, Utc::now())
+        .insert(&store.db)
+        .await
+        .expect("hash to be inserted");
+}
+
+#[tokio::test]
+async fn tagging_stores_the_languages_of_tags_and_replies() {
+    let server = telegram_server().await;
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    insert_unhashable(&store, &sticker).await;
+
+    let text = "cat zh:貓";
+    let message = message(json!({ "text": format!("/tag {text}") }));
+    let target = TagTarget::Indexed(sticker.id);
+    tag_sticker(
+        test_bot(&server),
+        message,
+        store.clone(),
+        tagger,
+        target,
+        text,
+    )
+    .await
+    .expect("tagging to succeed");
+
+    let tags = model::tagged_sticker::Entity::find()
+        .filter(model::tagged_sticker::Column::StickerId.eq(sticker.id))
+        .order_by_asc(model::tagged_sticker::Column::Id)
+        .all(&store.db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|tagged| (tagged.tag, tagged.language))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tags,
+        [
+            ("cat".to_owned(), None),
+            ("貓".to_owned(), Some("zh".to_owned()))
+        ]
+    );
+
+    let replies = sent_requests(&server, "sendMessage").await;
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0]["chat_id"], TAGGER_ID);
+    assert!(replies[0]["text"].as_str().unwrap().contains("貓"));
+}
+
+#[tokio::test]
+async fn untagging_removes_only_the_own_tags_of_the_tagger() {
+    let server = telegram_server().await;
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let other_tagger = insert_tagger(&store, TAGGER_ID + 1, "other").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    insert_tags(&store, &tagger, &sticker, "cat happy").await;
+    insert_tags(&store, &other_tagger, &sticker, "cute").await;
+
+    let message = message(json!({
+        "text": "/untag cat cute",
+        "reply_to_message": message_json(json!({ "sticker": sticker_json("sticker") })),
+    }));
+    handle_untag_command(
+        test_bot(&server),
+        message,
+        store.clone(),
+        tagger,
+        "cat cute".to_owned(),
+    )
+    .await
+    .expect("untagging to succeed");
+
+    assert_eq!(tags_of(&store, &sticker).await, ["happy", "cute"]);
+    assert_eq!(sent_requests(&server, "sendMessage").await.len(), 1);
+}
+
+#[tokio::test]
+async fn custom_emoji_in_replied_messages_are_indexed_and_tagged() {
+    let server = telegram_server().await;
+    let mut custom_emoji = sticker_json("emoji");
+    custom_emoji["type"] = json!("custom_emoji");
+    custom_emoji["custom_emoji_id"] = json!("5368324170671202286");
+    custom_emoji["set_name"] = json!("cat_emoji");
+    Mock::given(method("POST"))
+        .and(path_regex("/getCustomEmojiStickers$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ok": true,
+            "result": [custom_emoji],
+        })))
+        .mount(&server)
+        .await;
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+
+    let message = message(json!({
+        "text": "/tag cat",
+        "reply_to_message": message_json(json!({
+            "text": "😺",
+            "entities": [{
+                "type": "custom_emoji",
+                "offset": 0,
+                "length": 2,
+                "custom_emoji_id": "5368324170671202286",
+            }],
+        })),
+    }));
+    handle_tag_command(
+        test_bot(&server),
+        message,
+        store.clone(),
+        tagger,
+        "cat".to_owned(),
+    )
+    .await
+    .expect("tagging to succeed");
+
+    let emoji = model::sticker::Entity::find()
+        .one(&store.db)
+        .await
+        .unwrap()
+        .expect("custom emoji to be indexed");
+    assert_eq!(emoji.file_unique_id, "emoji");
+    assert_eq!(emoji.set_name, "cat_emoji");
+    assert_eq!(emoji.kind, model::sticker::CUSTOM_EMOJI);
+    assert_eq!(tags_of(&store, &emoji).await, ["cat"]);
+}
+
+#[tokio::test]
+async fn inline_results_are_identified_by_encoded_unique_ids() {
+    let server = telegram_server().await;
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let sticker = insert_sticker(&store, "AgADsticker", "cats", 0).await;
+    insert_tags(&store, &tagger, &sticker, "cat").await;
+
+    let inline_query = json!({
+        "id": "query",
+        "from": user_json(),
+        "query": "cat",
+        "offset": "",
+        "chat_type": "sender",
+    });
+    let update = serde_json::from_value::<Update>(json!({
+        "update_id": 1,
+        "inline_query": inline_query,
+    }))
+    .unwrap();
+    let inline_query = serde_json::from_value::<InlineQuery>(inline_query).unwrap();
+    inline_query_handler(test_bot(&server), update, inline_query, store.clone())
+        .await
+        .expect("inline query to be answered");
+
+    let answers = sent_requests(&server, "answerInlineQuery").await;
+    assert_eq!(answers.len(), 1);
+    let results = answers[0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["id"], result_id("AgADsticker"));
+    assert_eq!(results[0]["sticker_file_id"], "file-AgADsticker");
+}
+
+#[tokio::test]
+async fn inline_queries_superseded_while_typing_are_skipped() {
+    let server = telegram_server().await;
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    insert_tags(&store, &tagger, &sticker, "cat").await;
+
+    let inline_query = |id: &str, query: &str| {
+        let inline_query = json!({
+            "id": id,
+            "from": user_json(),
+            "query": query,
+            "offset": "",
+            "chat_type": "sender",
+        });
+        let update = serde_json::from_value::<Update>(json!({
+            "update_id": 1,
+            "inline_query": inline_query,
+        }))
+        .unwrap();
+        let inline_query = serde_json::from_value::<InlineQuery>(inline_query).unwrap();
+        inline_query_handler(test_bot(&server), update, inline_query, store.clone())
+    };
+    let (typing, typed) = tokio::join!(inline_query("1", "ca"), inline_query("2", "cat"));
+    typing.expect("superseded inline query to be skipped");
+    typed.expect("inline query to be answered");
+
+    let answers = sent_requests(&server, "answerInlineQuery").await;
+    assert_eq!(answers.len(), 1);
+    assert_eq!(answers[0]["inline_query_id"], "2");
+}
+
+#[tokio::test]
+async fn settings_change_the_inline_results_of_the_presser() {
+    let server = telegram_server().await;
+    Mock::given(method("POST"))
+        .and(path_regex("/answerCallbackQuery$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ok": true,
+            "result": true,
+        })))
+        .mount(&server)
+        .await;
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    for (file_unique_id, popularity) in [("old", 0), ("popular", 10), ("new", 5)] {
+        let sticker = insert_sticker(&store, file_unique_id, "cats", popularity).await;
+        insert_tags(&store, &tagger, &sticker, "cat").await;
+    }
+    let nsfw = insert_sticker(&store, "nsfw", "cats", 0).await;
+    insert_tags(&store, &tagger, &nsfw, "cat nsfw").await;
+
+    // the buttons are pressed on a message that's gone, so only the settings change
+    let presses = [
+        (1, "settings:sort:recency"),
+        (2, "settings:page:2"),
+        (3, "settings:safe:on"),
+    ];
+    for (update_id, data) in presses {
+        let query = json!({
+            "id": format!("callback{update_id}"),
+            "from": user_json(),
+            "chat_instance": "instance",
+            "data": data,
+        });
+        let update = serde_json::from_value::<Update>(json!({
+            "update_id": update_id,
+            "callback_query": query,
+        }))
+        .unwrap();
+        let query = serde_json::from_value::<CallbackQuery>(query).unwrap();
+        callback_query_handler(test_bot(&server), update, query, store.clone())
+            .await
+            .expect("settings to be saved");
+    }
+    assert_eq!(sent_requests(&server, "answerCallbackQuery").await.len(), 3);
+
+    let inline_query = json!({
+        "id": "query",
+        "from": user_json(),
+        "query": "cat",
+        "offset": "",
+        "chat_type": "sender",
+    });
+    let update = serde_json::from_value::<Update>(json!({
+        "update_id": 4,
+        "inline_query": inline_query,
+    }))
+    .unwrap();
+    let inline_query = serde_json::from_value::<InlineQuery>(inline_query).unwrap();
+    inline_query_handler(test_bot(&server), update, inline_query, store.clone())
+        .await
+        .expect("inline query to be answered");
+
+    // the most recently indexed stickers come first, rather than the most popular, except for the
+    // ones hidden by safe search
+    let answers = sent_requests(&server, "answerInlineQuery").await;
+    let results = answers[0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["id"], result_id("new"));
+    assert_eq!(results[1]["id"], result_id("popular"));
+    assert_eq!(answers[0]["next_offset"], "2");
+    assert_eq!(answers[0]["is_personal"], true);
+}
+
+#[tokio::test]
+async fn animations_are_answered_with_gif_results() {
+    let server = telegram_server().await;
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let animation = insert_sticker(&store, "AgADanimation", "", 0).await;
+    let mut active_animation = animation.clone().into_active_model();
+    active_animation.kind = Set(model::sticker::ANIMATION.to_owned());
+    active_animation.update(&store.db).await.unwrap();
+    insert_tags(&store, &tagger, &animation, "cat").await;
+
+    let inline_query = json!({
+        "id": "query",
+        "from": user_json(),
+        "query": "cat",
+        "offset": "",
+    });
+    let update = serde_json::from_value::<Update>(json!({
+        "update_id": 1,
+        "inline_query": inline_query,
+    }))
+    .unwrap();
+    let inline_query = serde_json::from_value::<InlineQuery>(inline_query).unwrap();
+    inline_query_handler(test_bot(&server), update, inline_query, store.clone())
+        .await
+        .expect("inline query to be answered");
+
+    let answers = sent_requests(&server, "answerInlineQuery").await;
+    let results = answers[0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["type"], "gif");
+    assert_eq!(results[0]["gif_file_id"], "file-AgADanimation");
+}
+
+#[tokio::test]
+async fn photos_are_indexed_without_a_set_and_answered_as_photos() {
+    let server = telegram_server().await;
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+
+    let photo_size = |file_unique_id: &str, size: u32| {
+        json!({
+            "file_id": format!("file-{file_unique_id}"),
+            "file_unique_id": file_unique_id,
+            "width": size,
+            "height": size,
+        })
+    };
+    let message = message(json!({
+        "text": "/tag cat",
+        "reply_to_message": message_json(json!({
+            "photo": [photo_size("small", 90), photo_size("AgADphoto", 1280)],
+        })),
+    }));
+    handle_tag_command(
+        test_bot(&server),
+        message,
+        store.clone(),
+        tagger,
+        "cat".to_owned(),
+    )
+    .await
+    .expect("tagging to succeed");
+
+    let photo = model::sticker::Entity::find()
+        .one(&store.db)
+        .await
+        .unwrap()
+        .expect("photo to be indexed");
+    assert_eq!(photo.file_unique_id, "AgADphoto");
+    assert_eq!(photo.kind, model::sticker::PHOTO);
+    assert_eq!(photo.set_name, "");
+    assert_eq!(photo.width, Some(1280));
+
+    let inline_query = json!({
+        "id": "query",
+        "from": user_json(),
+        "query": "cat",
+        "offset": "",
+    });
+    let update = serde_json::from_value::<Update>(json!({
+        "update_id": 1,
+        "inline_query": inline_query,
+    }))
+    .unwrap();
+    let inline_query = serde_json::from_value::<InlineQuery>(inline_query).unwrap();
+    inline_query_handler(test_bot(&server), update, inline_query, store.clone())
+        .await
+        .expect("inline query to be answered");
+
+    let answers = sent_requests(&server, "answerInlineQuery").await;
+    let results = answers[0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["type"], "photo");
+    assert_eq!(results[0]["photo_file_id"], "file-AgADphoto");
+}
+
+#[tokio::test]
+async fn tags_of_a_replied_gif_are_listed_without_a_set_link() {
+    let server = telegram_server().await;
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let animation = insert_sticker(&store, "AgADanimation", "", 0).await;
+    let mut active_animation = animation.clone().into_active_model();
+    active_animation.kind = Set(model::sticker::ANIMATION.to_owned());
+    active_animation.update(&store.db).await.unwrap();
+    insert_tags(&store, &tagger, &animation, "cat").await;
+
+    let message = message(json!({
+        "text": "/listtags",
+        "reply_to_message": message_json(json!({
+            "animation": {
+                "file_id": "file-AgADanimation",
+                "file_unique_id": "AgADanimation",
+                "width": 320,
+                "height": 240,
+                "duration": 3,
+            },
+        })),
+    }));
+    handle_list_tags_command(test_bot(&server), message, store.clone(), String::new())
+        .await
+        .expect("listing tags to succeed");
+
+    let sent = sent_requests(&server, "sendMessage").await;
+    let text = sent[0]["text"].as_str().unwrap();
+    assert!(text.contains("cat"), "{text}");
+    assert!(text.contains("addstickers") == false, "{text}");
+}
+
+#[tokio::test]
+async fn tagged_duplicates_are_found_by_a_shared_band_of_their_hashes() {
+    let server = telegram_server().await;
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let hash: u64 = 0x0123_4567_89ab_cdef;
+    // a bit flipped in each band but the last one, or in every one of them
+    let near = hash ^ (1 | 1 << 13 | 1 << 26 | 1 << 39);
+    let far = near ^ (1 << 52);
+    let sticker = insert_sticker(&store, "sticker", "new", 0).await;
+    let lookalike = insert_sticker(&store, "lookalike", "old", 0).await;
+    let other = insert_sticker(&store, "other", "old", 0).await;
+    for (sticker, hash) in [(&sticker, hash), (&lookalike, near), (&other, far)] {
+        model::sticker_hash::new_active_model(sticker.id, Some(hash), Utc::now())
+            .insert(&store.db)
+            .await
+            .expect("hash to be inserted");
+    }
+    insert_tags(&store, &tagger, &lookalike, "cat").await;
+    insert_tags(&store, &tagger, &other, "cat").await;
+
+    let duplicates = find_tagged_duplicates(&test_bot(&server), &store, sticker.id)
+        .await
+        .expect("looking for duplicates to succeed");
+    assert_eq!(
+        duplicates
+            .iter()
+            .map(|sticker| sticker.id)
+            .collect::<Vec<_>>(),
+        [lookalike.id]
+    );
+}
+
+#[tokio::test]
+async fn removed_tags_are_kept_in_the_trash_until_restored() {
+    let server = telegram_server().await;
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    insert_tags(&store, &tagger, &sticker, "cat happy").await;
+    let reply_to_sticker = |text: &str| {
+        message(json!({
+            "text": text,
+            "reply_to_message": message_json(json!({ "sticker": sticker_json("sticker") })),
+        }))
+    };
+
+    handle_untag_command(
+        test_bot(&server),
+        reply_to_sticker("/untag cat"),
+        store.clone(),
+        tagger,
+        "cat".to_owned(),
+    )
+    .await
+    .expect("untagging to succeed");
+    assert_eq!(tags_of(&store, &sticker).await, ["happy"]);
+    let trashed = model::tagged_sticker::Entity::find()
+        .filter(model::tagged_sticker::Column::DeletedAt.is_not_null())
+        .one(&store.db)
+        .await
+        .unwrap()
+        .expect("removed tag to be in the trash");
+    assert_eq!(trashed.tag, "cat");
+    assert_eq!(trashed.deleted_by, Some(TAGGER_ID));
+
+    handle_restore_command(
+        test_bot(&server),
+        reply_to_sticker("/restore"),
+        store.clone(),
+        TAGGER_ID,
+        String::new(),
+    )
+    .await
+    .expect("restoring to succeed");
+    assert_eq!(tags_of(&store, &sticker).await, ["cat", "happy"]);
+    assert_eq!(sent_requests(&server, "sendMessage").await.len(), 2);
+}
+
+#[tokio::test]
+async fn tags_added_again_are_voted_for_and_removed_when_voted_down() {
+    let server = telegram_server().await;
+    let store = test_store_with("tag_removal_balance = 0").await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let other_tagger = insert_tagger(&store, TAGGER_ID + 1, "other").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    insert_unhashable(&store, &sticker).await;
+    insert_tags(&store, &other_tagger, &sticker, "cat happy").await;
+    let tag_ids = model::tagged_sticker::Entity::find()
+        .filter(model::tagged_sticker::Column::StickerId.eq(sticker.id))
+        .order_by_asc(model::tagged_sticker::Column::Id)
+        .all(&store.db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|tagged| tagged.id)
+        .collect::<Vec<_>>();
+
+    // the tagger adding a tag that the sticker has already votes for it instead
+    tag_sticker(
+        test_bot(&server),
+        message(json!({ "text": "/tag cat" })),
+        store.clone(),
+        tagger,
+        TagTarget::Indexed(sticker.id),
+        "cat",
+    )
+    .await
+    .expect("tagging to succeed");
+    let balances = vote_balances(&store.db, tag_ids.clone()).await.unwrap();
+    assert_eq!((balances[&tag_ids[0]], balances[&tag_ids[1]]), (2, 1));
+
+    handle_vote_command(
+        test_bot(&server),
+        message(json!({
+            "text": "/vote down happy",
+            "reply_to_message": message_json(json!({ "sticker": sticker_json("sticker") })),
+        })),
+        store.clone(),
+        TAGGER_ID,
+        "down happy".to_owned(),
+    )
+    .await
+    .expect("voting to succeed");
+    assert_eq!(tags_of(&store, &sticker).await, ["cat"]);
+    assert_eq!(sent_requests(&server, "sendMessage").await.len(), 2);
+}
+
+#[tokio::test]
+async fn private_tags_of_others_neither_block_nor_show_through_the_same_tag() {
+    let server = telegram_server().await;
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let tagger_id = tagger.user.id;
+    let other = insert_tagger(&store, TAGGER_ID + 1, "other").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    insert_unhashable(&store, &sticker).await;
+    let private_tag = model::tagged_sticker::ActiveModel {
+        tag: Set("grumpy".to_owned()),
+        sticker_id: Set(sticker.id),
+        tagger_id: Set(other.user.id),
+        ts: Set(Utc::now()),
+        approved: Set(true),
+        visibility: Set(model::tagged_sticker::PRIVATE.to_owned()),
+        scope: Set(model::tagged_sticker::scope(
+            None,
+            model::tagged_sticker::PRIVATE,
+            other.user.id,
+        )),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .unwrap();
+
+    tag_sticker(
+        test_bot(&server),
+        message(json!({ "text": "/tag grumpy" })),
+        store.clone(),
+        tagger,
+        TagTarget::Indexed(sticker.id),
+        "grumpy",
+    )
+    .await
+    .expect("tagging to succeed");
+
+    // the tag is added in public next to the private one, which isn't voted for
+    let tagged = model::tagged_sticker::Entity::find()
+        .filter(model::tagged_sticker::Column::StickerId.eq(sticker.id))
+        .order_by_asc(model::tagged_sticker::Column::Id)
+        .all(&store.db)
+        .await
+        .unwrap();
+    assert_eq!(tagged.len(), 2);
+    assert_eq!(tagged[1].tag, "grumpy");
+    assert_eq!(tagged[1].tagger_id, tagger_id);
+    assert_eq!(tagged[1].visibility, model::tagged_sticker::PUBLIC);
+    let balances = vote_balances(&store.db, [private_tag.id]).await.unwrap();
+    assert_eq!(balances[&private_tag.id], 1);
+
+    let sent = sent_requests(&server, "sendMessage").await;
+    let text = sent[0]["text"].as_str().unwrap();
+    assert!(text.starts_with(strings::TAGGED_STICKER));
+    assert!(text.contains(strings::TAGS_VOTED) == false);
+    assert!(text.contains(strings::TAGS_ALREADY_PRESENT) == false);
+}
+
+#[tokio::test]
+async fn tags_scoped_to_a_group_dont_block_the_same_tag_for_everyone() {
+    let server = telegram_server().await;
+    let store = test_store_with("group_scoped_tags = true").await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let other = insert_tagger(&store, TAGGER_ID + 1, "other").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    insert_unhashable(&store, &sticker).await;
+    let group_tag = model::tagged_sticker::ActiveModel {
+        tag: Set("joke".to_owned()),
+        sticker_id: Set(sticker.id),
+        tagger_id: Set(other.user.id),
+        ts: Set(Utc::now()),
+        approved: Set(true),
+        chat_id: Set(Some(-100)),
+        scope: Set(model::tagged_sticker::scope(
+            Some(-100),
+            model::tagged_sticker::PUBLIC,
+            other.user.id,
+        )),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .unwrap();
+
+    // tagged in the private chat with the bot, outside of the group
+    tag_sticker(
+        test_bot(&server),
+        message(json!({ "text": "/tag joke" })),
+        store.clone(),
+        tagger,
+        TagTarget::Indexed(sticker.id),
+        "joke",
+    )
+    .await
+    .expect("tagging to succeed");
+
+    let tagged = model::tagged_sticker::Entity::find()
+        .filter(model::tagged_sticker::Column::StickerId.eq(sticker.id))
+        .order_by_asc(model::tagged_sticker::Column::Id)
+        .all(&store.db)
+        .await
+        .unwrap();
+    assert_eq!(tagged.len(), 2);
+    assert_eq!(tagged[1].tag, "joke");
+    assert_eq!(tagged[1].chat_id, None);
+    let balances = vote_balances(&store.db, [group_tag.id]).await.unwrap();
+    assert_eq!(balances[&group_tag.id], 1);
+
+    let sent = sent_requests(&server, "sendMessage").await;
+    let text = sent[0]["text"].as_str().unwrap();
+    assert!(text.starts_with(strings::TAGGED_STICKER));
+    assert!(text.contains(strings::TAGS_VOTED) == false);
+}
+
+#[tokio::test]
+async fn memberships_are_only_looked_up_in_groups_with_matching_tags_and_remembered() {
+    let server = telegram_server().await;
+    // the user is in group -200, while the bot can't look into group -100
+    Mock::given(method("POST"))
+        .and(path_regex("/getChatMember$"))
+        .and(body_partial_json(json!({ "chat_id": -200 })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ok": true,
+            "result": { "status": "member", "user": user_json() },
+        })))
+        .mount(&server)
+        .await;
+    let store = test_store_with("group_scoped_tags = true").await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    for (tag, chat_id) in [("joke", -100), ("jokes", -200), ("meme", -300)] {
+        model::tagged_sticker::ActiveModel {
+            tag: Set(tag.to_owned()),
+            sticker_id: Set(sticker.id),
+            tagger_id: Set(tagger.user.id),
+            ts: Set(Utc::now()),
+            approved: Set(true),
+            chat_id: Set(Some(chat_id)),
+            scope: Set(model::tagged_sticker::scope(
+                Some(chat_id),
+                model::tagged_sticker::PUBLIC,
+                tagger.user.id,
+            )),
+            ..Default::default()
+        }
+        .insert(&store.db)
+        .await
+        .unwrap();
+    }
+
+    let bot = test_bot(&server);
+    let search_query = query::SearchQuery::parse("joke");
+    for _ in 0..2 {
+        let chats = searchable_chats(&bot, &store, UserId(TAGGER_ID as u64), &search_query)
+            .await
+            .unwrap();
+        assert_eq!(chats, [-200]);
+    }
+    // group -300 has no tags matching the query, and the failed lookup is remembered as well
+    let lookups = sent_requests(&server, "getChatMember").await;
+    assert_eq!(lookups.len(), 2);
+    assert!(lookups.iter().all(|lookup| lookup["chat_id"] != -300));
+}
+
+#[tokio::test]
+async fn chown_moves_the_tags_of_a_user_to_another() {
+    let server = telegram_server().await;
+    let store = test_store().await;
+    let old = insert_tagger(&store, TAGGER_ID + 1, "old").await;
+    let new = insert_tagger(&store, TAGGER_ID + 2, "new").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    insert_tags(&store, &old, &sticker, "cat happy").await;
+    insert_tags(&store, &new, &sticker, "cute").await;
+
+    let text = "secret old new";
+    let message = message(json!({ "text": format!("/chown {text}") }));
+    handle_chown_command(test_bot(&server), message, store.clone(), text.to_owned())
+        .await
+        .expect("chown to succeed");
+
+    let tagger_ids = model::tagged_sticker::Entity::find()
+        .filter(model::tagged_sticker::Column::StickerId.eq(sticker.id))
+        .all(&store.db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|tagged| tagged.tagger_id)
+        .collect::<Vec<_>>();
+    assert_eq!(tagger_ids, [new.user.id; 3]);
+    let audit = model::audit_log::Entity::find()
+        .one(&store.db)
+        .await
+        .unwrap()
+        .expect("chown to be audited");
+    assert_eq!(audit.action, model::audit_log::CHOWN);
+    assert_eq!(sent_requests(&server, "sendMessage").await.len(), 1);
+}
+
+#[tokio::test]
+async fn retag_renames_a_tag_unless_the_sticker_has_the_new_one() {
+    let server = telegram_server().await;
+    // the tagger moderates in their private chat with the bot
+    let store = test_store_with(&format!("admin_chat_id = {TAGGER_ID}")).await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let cat = insert_sticker(&store, "cat", "cats", 0).await;
+    let kitty = insert_sticker(&store, "kitty", "cats", 0).await;
+    insert_tags(&store, &tagger, &cat, "cat happy").await;
+    insert_tags(&store, &tagger, &kitty, "cat kitty").await;
+    let retag = |text: &str| {
+        let message = message(json!({ "text": format!("/retag {text}") }));
+        handle_retag_command(test_bot(&server), message, store.clone(), text.to_owned())
+    };
+
+    retag("cat kitty --dry-run")
+        .await
+        .expect("dry run to succeed");
+    assert_eq!(tags_of(&store, &cat).await, ["cat", "happy"]);
+    assert_eq!(tags_of(&store, &kitty).await, ["cat", "kitty"]);
+
+    retag("cat kitty").await.expect("retagging to succeed");
+    assert_eq!(tags_of(&store, &cat).await, ["kitty", "happy"]);
+    assert_eq!(tags_of(&store, &kitty).await, ["kitty"]);
+    let audit = model::audit_log::Entity::find()
+        .one(&store.db)
+        .await
+        .unwrap()
+        .expect("retag to be audited");
+    assert_eq!(audit.action, model::audit_log::RETAG);
+    assert_eq!(sent_requests(&server, "sendMessage").await.len(), 2);
+}
+
+#[tokio::test]
+async fn merge_moves_the_tags_of_a_duplicate_picked_first() {
+    let server = telegram_server().await;
+    let store = test_store_with(&format!("admin_chat_id = {TAGGER_ID}")).await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 3).await;
+    let duplicate = insert_sticker(&store, "duplicate", "cats_old", 2).await;
+    insert_tags(&store, &tagger, &sticker, "cat").await;
+    insert_tags(&store, &tagger, &duplicate, "cat happy").await;
+    let merge = |file_unique_id: &str| {
+        let message = message(json!({
+            "text": "/merge",
+            "reply_to_message": message_json(json!({ "sticker": sticker_json(file_unique_id) })),
+        }));
+        handle_merge_command(test_bot(&server), message, store.clone(), String::new())
+    };
+
+    merge("duplicate")
+        .await
+        .expect("picking the duplicate to succeed");
+    merge("sticker").await.expect("merging to succeed");
+
+    assert_eq!(tags_of(&store, &sticker).await, ["cat", "happy"]);
+    let merged = model::sticker::Entity::find().all(&store.db).await.unwrap();
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[0].file_unique_id, "sticker");
+    assert_eq!(merged[0].popularity, 5);
+    assert_eq!(sent_requests(&server, "sendMessage").await.len(), 2);
+}
+
+#[tokio::test]
+async fn chosen_results_count_towards_the_usage_of_the_user() {
+    let server = telegram_server().await;
+    let store = test_store().await;
+    let sticker = insert_sticker(&store, "AgADsticker", "cats", 0).await;
+
+    let chosen = json!({
+        "result_id": result_id("AgADsticker"),
+        "from": user_json(),
+        "query": "cat",
+    });
+    for update_id in [1, 2] {
+        let update = serde_json::from_value::<Update>(json!({
+            "update_id": update_id,
+            "chosen_inline_result": chosen,
+        }))
+        .unwrap();
+        let chosen = serde_json::from_value::<ChosenInlineResult>(chosen.clone()).unwrap();
+        chosen_inline_result_handler(test_bot(&server), update, chosen, store.clone())
+            .await
+            .expect("chosen result to be recorded");
+    }
+
+    let usage = model::sticker_usage::Entity::find()
+        .filter(model::sticker_usage::Column::UserId.eq(TAGGER_ID))
+        .filter(model::sticker_usage::Column::StickerId.eq(sticker.id))
+        .one(&store.db)
+        .await
+        .unwrap()
+        .expect("usage to be recorded");
+    assert_eq!(usage.times_chosen, 2);
+    // choosing a result doesn't talk to telegram
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn backups_beyond_the_retained_ones_are_deleted_from_the_chat() {
+    let server = telegram_server().await;
+    Mock::given(method("POST"))
+        .and(path_regex("/sendDocument$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ok": true,
+            "result": message_json(json!({ "text": "backup" })),
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path_regex("/deleteMessage$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ok": true,
+            "result": true,
+        })))
+        .mount(&server)
+        .await;
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    insert_tags(&store, &tagger, &sticker, "cat").await;
+    // too old for the bot to delete, so it's only pruned from the log
+    model::backup_log::ActiveModel {
+        chat_id: Set(TAGGER_ID),
+        message_id: Set(1),
+        file_name: Set("stickers-backup-old.json".to_owned()),
+        size: Set(1),
+        ts: Set(Utc::now() - chrono::Duration::days(3)),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .unwrap();
+
+    for _ in 0..3 {
+        backup::run(&test_bot(&server), &store.db, ChatId(TAGGER_ID), 2)
+            .await
+            .expect("backup to be sent");
+    }
+
+    let backups = model::backup_log::Entity::find()
+        .all(&store.db)
+        .await
+        .unwrap();
+    assert_eq!(backups.len(), 2);
+    // the documents are uploaded as multipart forms, which aren't json
+    let documents_sent = server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|request| request.url.path().ends_with("/sendDocument"))
+        .count();
+    assert_eq!(documents_sent, 3);
+    assert_eq!(sent_requests(&server, "deleteMessage").await.len(), 1);
+}
+
+#[tokio::test]
+async fn admins_allow_and_deny_users_without_the_secret() {
+    let server = telegram_server().await;
+    let store = test_store_with(&format!("admin_user_ids = [{TAGGER_ID}]")).await;
+    model::user::ActiveModel {
+        user_id: Set(TAGGER_ID + 1),
+        username: Set("someone".to_owned()),
+        allowed: Set(false),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .unwrap();
+    let is_allowed = || async {
+        model::user::Entity::find()
+            .filter(model::user::Column::Username.eq("someone"))
+            .one(&store.db)
+            .await
+            .unwrap()
+            .expect("user to be registered")
+            .allowed
+    };
+
+    handle_allow_command(
+        test_bot(&server),
+        message(json!({ "text": "/allow someone" })),
+        store.clone(),
+        "someone".to_owned(),
+    )
+    .await
+    .expect("allowing to succeed");
+    assert!(is_allowed().await);
+
+    // everyone else still needs the secret
+    let mut stranger = user_json();
+    stranger["id"] = json!(TAGGER_ID + 2);
+    handle_deny_command(
+        test_bot(&server),
+        message(json!({ "text": "/deny someone", "from": stranger.clone() })),
+        store.clone(),
+        "someone".to_owned(),
+        false,
+    )
+    .await
+    .expect("denying to be refused");
+    assert!(is_allowed().await);
+    handle_deny_command(
+        test_bot(&server),
+        message(json!({ "text": "/deny secret someone", "from": stranger })),
+        store.clone(),
+        "secret someone".to_owned(),
+        false,
+    )
+    .await
+    .expect("denying to succeed");
+    assert!(is_allowed().await == false);
+    assert_eq!(sent_requests(&server, "sendMessage").await.len(), 3);
+
+    // and so do the other admin commands
+    handle_audit_command(
+        test_bot(&server),
+        message(json!({ "text": "/audit 5" })),
+        store.clone(),
+        "5".to_owned(),
+    )
+    .await
+    .expect("the audit log to be shown");
+    let sent = sent_requests(&server, "sendMessage").await;
+    assert_eq!(sent.len(), 4);
+    assert!(sent[3]["text"]
+        .as_str()
+        .unwrap()
+        .contains(model::audit_log::DENY));
+}
+
+#[tokio::test]
+async fn messages_containing_a_secret_are_deleted() {
+    let server = telegram_server().await;
+    Mock::given(method("POST"))
+        .and(path_regex("/deleteMessage$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ok": true,
+            "result": true,
+        })))
+        .mount(&server)
+        .await;
+    // the secret of the tests is rotated out in favor of a new one
+    let store = test_store_with("stickers_secrets = [\"new\"]").await;
+    model::user::ActiveModel {
+        user_id: Set(TAGGER_ID + 1),
+        username: Set("someone".to_owned()),
+        allowed: Set(false),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .unwrap();
+
+    for (update_id, text) in [(1, "/allow new someone"), (2, "/allow wrong someone")] {
+        let message = message_json(json!({ "text": text }));
+        let update = serde_json::from_value::<Update>(json!({
+            "update_id": update_id,
+            "message": message,
+        }))
+        .unwrap();
+        let message = serde_json::from_value::<Message>(message).unwrap();
+        command_handler(test_bot(&server), update, message, store.clone())
+            .await
+            .expect("command to be handled");
+    }
+
+    let someone = model::user::Entity::find()
+        .filter(model::user::Column::Username.eq("someone"))
+        .one(&store.db)
+        .await
+        .unwrap()
+        .expect("user to be registered");
+    assert!(someone.allowed);
+    assert_eq!(sent_requests(&server, "sendMessage").await.len(), 2);
+    assert_eq!(sent_requests(&server, "deleteMessage").await.len(), 1);
+}
+
+#[tokio::test]
+async fn start_offers_the_most_popular_tags_as_example_searches() {
+    let server = telegram_server().await;
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID, "tagger").await;
+    for (i, tags) in ["cat dog cow", "cat dog", "cat ant"]
+        .into_iter()
+        .enumerate()
+    {
+        let sticker = insert_sticker(&store, &format!("unique-{i}"), "set", 0).await;
+        insert_tags(&store, &tagger, &sticker, tags).await;
+    }
+
+    // the tag suggestions of inline queries start the bot with a parameter
+    let message = message_json(json!({ "text": "/start tags" }));
+    let update = serde_json::from_value::<Update>(json!({
+        "update_id": 1,
+        "message": message,
+    }))
+    .unwrap();
+    let message = serde_json::from_value::<Message>(message).unwrap();
+    command_handler(test_bot(&server), update, message, store.clone())
+        .await
+        .expect("command to be handled");
+
+    let sent = sent_requests(&server, "sendMessage").await;
+    assert_eq!(sent.len(), 1);
+    let text = sent[0]["text"].as_str().unwrap();
+    assert!(text.contains("/register"));
+    assert!(text.contains("/help"));
+    assert_eq!(
+        sent[0]["reply_markup"]["inline_keyboard"],
+        json!([
+            [{ "text": "cat", "switch_inline_query_current_chat": "cat" }],
+            [{ "text": "dog", "switch_inline_query_current_chat": "dog" }],
+            [{ "text": "ant", "switch_inline_query_current_chat": "ant" }],
+            [{ "text": "Search in a chat", "switch_inline_query": "" }],
+        ])
+    );
+}
+
+#[tokio::test]
+async fn commands_are_registered_for_each_kind_of_chat() {
+    let server = telegram_server().await;
+    Mock::given(method("POST"))
+        .and(path_regex("/setMyCommands$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ok": true,
+            "result": true,
+        })))
+        .mount(&server)
+        .await;
+
+    commands::register(&test_bot(&server), Some(ChatId(-100)))
+        .await
+        .expect("commands to be registered");
+
+    let sent = sent_requests(&server, "setMyCommands").await;
+    let commands_of = |scope: Value| {
+        let request = sent
+            .iter()
+            .find(|request| request["scope"] == scope)
+            .expect("commands to be set for the scope");
+        request["commands"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|command| command["command"].as_str().unwrap().to_owned())
+            .collect::<Vec<_>>()
+    };
+    let private = commands_of(json!({ "type": "all_private_chats" }));
+    assert!(private.contains(&"tag".to_owned()));
+    assert!(private.contains(&"register".to_owned()));
+    assert!(private.contains(&"allow".to_owned()) == false);
+    assert!(private.contains(&"start".to_owned()) == false);
+    let groups = commands_of(json!({ "type": "all_group_chats" }));
+    assert!(groups.contains(&"tag".to_owned()));
+    assert!(groups.contains(&"token".to_owned()) == false);
+    let admin_chat = commands_of(json!({ "type": "chat", "chat_id": -100 }));
+    assert!(admin_chat.contains(&"allow".to_owned()));
+    assert!(admin_chat.contains(&"review".to_owned()));
+}
+
+#[tokio::test]
+async fn approving_a_tag_under_review_is_audited_once() {
+    let server = telegram_server().await;
+    for api_method in ["answerCallbackQuery", "editMessageText"] {
+        let result = match api_method {
+            "editMessageText" => message_json(json!({ "text": "review" })),
+            _ => json!(true),
+        };
+        Mock::given(method("POST"))
+            .and(path_regex(format!("/{api_method}$")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "ok": true,
+                "result": result,
+            })))
+            .mount(&server)
+            .await;
+    }
+    let store = test_store().await;
+    let tagger = insert_tagger(&store, TAGGER_ID + 1, "newbie").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    insert_tags(&store, &tagger, &sticker, "cat").await;
+    let mut tagged = model::tagged_sticker::Entity::find()
+        .one(&store.db)
+        .await
+        .unwrap()
+        .expect("tag to be inserted")
+        .into_active_model();
+    tagged.approved = Set(false);
+    let tag_id = tagged.update(&store.db).await.unwrap().id.to_string();
+
+    // the second moderator pressing the button finds the tag reviewed already
+    for id in ["callback1", "callback2"] {
+        let query = serde_json::from_value::<CallbackQuery>(json!({
+            "id": id,
+            "from": user_json(),
+            "chat_instance": "instance",
+            "data": format!("reviewapprove:{tag_id}"),
+        }))
+        .unwrap();
+        let message = message(json!({ "text": "review" }));
+        handle_review_callback(
+            test_bot(&server),
+            query,
+            message,
+            store.clone(),
+            &tag_id,
+            true,
+        )
+        .await
+        .expect("review to succeed");
+    }
+
+    let tagged = model::tagged_sticker::Entity::find()
+        .one(&store.db)
+        .await
+        .unwrap()
+        .expect("tag to be kept");
+    assert!(tagged.approved);
+    let entries = model::audit_log::Entity::find()
+        .all(&store.db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|entry| (entry.action, entry.target_username))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        [(
+            model::audit_log::REVIEW_APPROVE.to_owned(),
+            Some("newbie".to_owned())
+        )]
+    );
+    assert_eq!(sent_requests(&server, "editMessageText").await.len(), 1);
+}
+
+#[tokio::test]
+async fn made_up_suggestions_of_private_tags_are_refused() {
+    let server = telegram_server().await;
+    Mock::given(method("POST"))
+        .and(path_regex("/answerCallbackQuery$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ok": true,
+            "result": true,
+        })))
+        .mount(&server)
+        .await;
+    let store = test_store().await;
+    insert_tagger(&store, TAGGER_ID, "tagger").await;
+    let other = insert_tagger(&store, TAGGER_ID + 1, "other").await;
+    let sticker = insert_sticker(&store, "sticker", "cats", 0).await;
+    let target = insert_sticker(&store, "target", "dogs", 0).await;
+    let private_tag = model::tagged_sticker::ActiveModel {
+        tag: Set("secret_mnemonic".to_owned()),
+        sticker_id: Set(sticker.id),
+        tagger_id: Set(other.user.id),
+        ts: Set(Utc::now()),
+        approved: Set(true),
+        visibility: Set(model::tagged_sticker::PRIVATE.to_owned()),
+        scope: Set(model::tagged_sticker::scope(
+            None,
+            model::tagged_sticker::PRIVATE,
+            other.user.id,
+        )),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .unwrap();
+
+    let query = json!({
+        "id": "callback",
+        "from": user_json(),
+        "chat_instance": "instance",
+        "data": format!("editapply:{}:{}", target.id, private_tag.id),
+    });
+    let update = serde_json::from_value::<Update>(json!({
+        "update_id": 1,
+        "callback_query": query,
+    }))
+    .unwrap();
+    let query = serde_json::from_value::<CallbackQuery>(query).unwrap();
+    callback_query_handler(test_bot(&server), update, query, store.clone())
+        .await
+        .expect("callback to be handled");
+
+    assert!(tags_of(&store, &target).await.is_empty());
+    let answers = sent_requests(&server, "answerCallbackQuery").await;
+    assert_eq!(answers.len(), 1);
+    assert_eq!(answers[0]["text"], "This suggestion is no longer available");
+}
+
+#[tokio::test]
+async fn registrations_are_only_approved_by_admins() {
+    let server = telegram_server().await;
+    Mock::given(method("POST"))
+        .and(path_regex("/answerCallbackQuery$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ok": true,
+            "result": true,
+        })))
+        .mount(&server)
+        .await;
+    // the tagger is in the admin chat, but isn't one of the admins
+    let store = test_store_with(&format!("admin_chat_id = {TAGGER_ID}")).await;
+    let applicant = model::user::ActiveModel {
+        user_id: Set(TAGGER_ID + 1),
+        username: Set("applicant".to_owned()),
+        allowed: Set(false),
+        propagate_tags: Set(false),
+        private_tags: Set(false),
+        ..Default::default()
+    }
+    .insert(&store.db)
+    .await
+    .unwrap();
+
+    let query = json!({
+        "id": "callback",
+        "from": user_json(),
+        "message": message_json(json!({ "text": "registration" })),
+        "chat_instance": "instance",
+        "data": format!("approve:{}", applicant.id),
+    });
+    let update = serde_json::from_value::<Update>(json!({
+        "update_id": 1,
+        "callback_query": query,
+    }))
+    .unwrap();
+    let query = serde_json::from_value::<CallbackQuery>(query).unwrap();
+    callback_query_handler(test_bot(&server), update, query, store.clone())
+        .await
+        .expect("callback to be handled");
+
+    let applicant = model::user::Entity::find_by_id(applicant.id)
+        .one(&store.db)
+        .await
+        .unwrap()
+        .expect("applicant to be kept");
+    assert!(applicant.allowed == false);
+    let answers = sent_requests(&server, "answerCallbackQuery").await;
+    assert_eq!(answers.len(), 1);
+    assert_eq!(answers[0]["text"], strings::NO_PERM);
+}
+
+/// Status code of the response of the webhook at `port` to the update posted along with `secret`
+async fn post_webhook_update(port: u16, secret: Option<&str>, update: Value) -> u16 {
+    let body = update.to_string();
+    let secret_header = secret
+        .map(|secret| format!("X-Telegram-Bot-Api-Secret-Token: {secret}\r\n"))
+        .unwrap_or_default();
+    let request = format!(
+        "POST /webhook HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+         Content-Length: {length}\r\n{secret_header}Connection: close\r\n\r\n{body}",
+        length = body.len()
+    );
+    let mut stream = TcpStream::connect(("127.0.0.1", port))
+        .await
+        .expect("webhook to be listening");
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .expect("request to be sent");
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .await
+        .expect("response to be received");
+    response
+        .split(' ')
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .expect("response to have a status code")
+}
+
+#[tokio::test]
+async fn webhook_rejects_updates_without_the_secret() {
+    let server = telegram_server().await;
+    Mock::given(method("POST"))
+        .and(path_regex("/setWebhook$"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "ok": true,
+            "result": true,
+        })))
+        .mount(&server)
+        .await;
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("a port to be free")
+        .port();
+    let url = url::Url::parse("https://example.com/webhook").unwrap();
+    let mut listener = webhook::listener(test_bot(&server), url, port, "secret".to_owned())
+        .await
+        .expect("webhook to be set");
+
+    let update = |update_id: i32| {
+        json!({
+            "update_id": update_id,
+            "message": message_json(json!({ "text": "/admin" })),
+        })
+    };
+    assert_eq!(post_webhook_update(port, None, update(1)).await, 401);
+    assert_eq!(
+        post_webhook_update(port, Some("guess"), update(2)).await,
+        401
+    );
+    assert_eq!(
+        post_webhook_update(port, Some("secret"), update(3)).await,
+        200
+    );
+
+    let set_webhook = sent_requests(&server, "setWebhook").await;
+    assert_eq!(set_webhook[0]["secret_token"], "secret");
+    // only the update sent along with the secret reaches the dispatcher
+    let received = listener
+        .as_stream()
+        .next()
+        .await
+        .expect("an update to be received")
+        .expect("update to be valid");
+    assert_eq!(received.id.0, 3);
+}
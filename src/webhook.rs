@@ -0,0 +1,95 @@
+//! Update listener receiving updates from Telegram via webhooks
+
+use std::{convert::Infallible, net::SocketAddr};
+
+use axum::{
+    body::Bytes,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Router,
+};
+use teloxide::{
+    prelude::*,
+    stop::{mk_stop_token, StopToken},
+    update_listeners::{StatefulListener, UpdateListener},
+};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tracing::{error, info, warn};
+use url::Url;
+
+use crate::{Bot, BotError};
+
+/// Header in which Telegram sends the secret given along with the webhook
+const SECRET_TOKEN_HEADER: &str = "X-Telegram-Bot-Api-Secret-Token";
+
+/// Registers `url` as the webhook of the bot, and starts an HTTP server listening on `port`.
+///
+/// The server only accepts updates on the path component of `url`, so that it can be placed
+/// behind a reverse proxy forwarding that path, and only the ones sent along with `secret`, so
+/// that nobody but Telegram can make them up.
+pub async fn listener(
+    bot: Bot,
+    url: Url,
+    port: u16,
+    secret: String,
+) -> Result<impl UpdateListener<Err = Infallible>, BotError> {
+    bot.set_webhook(url.clone())
+        .secret_token(secret.clone())
+        .send()
+        .await?;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let app = Router::new().route(
+        url.path(),
+        post(move |headers: HeaderMap, body: Bytes| {
+            let tx = tx.clone();
+            // the secret is checked before the update is even parsed
+            let authorized = headers
+                .get(SECRET_TOKEN_HEADER)
+                .map_or(false, |token| token.as_bytes() == secret.as_bytes());
+            async move {
+                if authorized == false {
+                    warn!("Rejecting a webhook update without the secret");
+                    return StatusCode::UNAUTHORIZED;
+                }
+                let update = match serde_json::from_slice::<Update>(&body) {
+                    Ok(update) => update,
+                    Err(e) => {
+                        warn!("Rejecting a malformed webhook update: {e}");
+                        return StatusCode::BAD_REQUEST;
+                    }
+                };
+                match tx.send(Ok(update)) {
+                    Ok(()) => StatusCode::OK,
+                    Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+                }
+            }
+        }),
+    );
+
+    let (stop_token, stop_flag) = mk_stop_token();
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let server = axum::Server::bind(&addr)
+        .serve(app.into_make_service())
+        .with_graceful_shutdown(stop_flag);
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            error!("Webhook server error: {e}");
+        }
+    });
+    info!(
+        "Listening for webhook updates on {addr}{path}",
+        path = url.path()
+    );
+
+    let stream = UnboundedReceiverStream::new(rx);
+    fn streamf<S, T>(state: &mut (S, T)) -> &mut S {
+        &mut state.0
+    }
+    Ok(StatefulListener::new(
+        (stream, stop_token),
+        streamf,
+        |state: &mut (_, StopToken)| state.1.clone(),
+    ))
+}